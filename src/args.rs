@@ -1,4 +1,8 @@
-use clap::{arg, Parser};
+use std::{env, ffi::OsString, fs};
+
+use clap::{arg, parser::ValueSource::CommandLine, Command, Parser};
+use solana_client::client_error::reqwest::header::{HeaderName, HeaderValue};
+use solana_sdk::pubkey::Pubkey;
 
 #[derive(Parser, Debug)]
 pub struct BalanceArgs {
@@ -8,6 +12,15 @@ pub struct BalanceArgs {
         help = "The address of the account to fetch the balance of"
     )]
     pub address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "text",
+        value_parser = ["text", "json"]
+    )]
+    pub output: String,
 }
 
 #[derive(Parser, Debug)]
@@ -20,10 +33,28 @@ pub struct BenchmarkArgs {
         default_value = "1"
     )]
     pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to run the benchmark for",
+        default_value = "30",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub duration: u64,
 }
 
 #[derive(Parser, Debug)]
-pub struct BussesArgs {}
+pub struct BussesArgs {
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        default_value = "text",
+        value_parser = ["text", "json"]
+    )]
+    pub output: String,
+}
 
 #[derive(Parser, Debug)]
 pub struct ClaimArgs {
@@ -40,14 +71,93 @@ pub struct ClaimArgs {
         help = "Wallet to receive claimed tokens."
     )]
     pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Claim all rewards for every keypair in this directory, each paying its own fees",
+        conflicts_with_all = ["amount", "to"]
+    )]
+    pub keypair_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "Simulate the transaction against the RPC and print the results instead of sending it"
+    )]
+    pub simulate_only: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the unsigned transaction to this file for signing offline, instead of sending it. The transaction expires with its blockhash, after about a minute.",
+        conflicts_with = "simulate_only"
+    )]
+    pub dump_unsigned: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Send a transaction signed offline, read from this file, and wait for it to confirm",
+        conflicts_with_all = ["simulate_only", "dump_unsigned"]
+    )]
+    pub submit_signed: Option<String>,
 }
 
 #[derive(Parser, Debug)]
-pub struct CloseArgs {}
+pub struct CloseArgs {
+    #[arg(short, long, help = "Close without asking for confirmation")]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        help = "Claim any remaining rewards and close in the same transaction"
+    )]
+    pub force: bool,
+}
 
 #[derive(Parser, Debug)]
 pub struct ConfigArgs {}
 
+#[derive(Parser, Debug)]
+pub struct DoctorArgs {}
+
+#[derive(Parser, Debug)]
+pub struct EstimateArgs {
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Your hashrate, as reported by the benchmark command. Measured before estimating if omitted."
+    )]
+    pub hashrate: Option<u64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to measure the hashrate with",
+        default_value_t = num_cpus::get() as u64
+    )]
+    pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to measure the hashrate for",
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The buffer time you mine with",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 pub struct PauseArgs {}
@@ -57,6 +167,19 @@ pub struct PauseArgs {}
 pub struct InitializeArgs {}
 
 #[derive(Parser, Debug)]
+pub struct InspectArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The authority of the proof account to inspect. Defaults to the signer."
+    )]
+    pub address: Option<String>,
+
+    #[arg(long, help = "Print the proof account as JSON")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
 pub struct MineArgs {
     // #[cfg(not(feature = "gpu"))]
     #[arg(
@@ -64,10 +187,13 @@ pub struct MineArgs {
         short,
         value_name = "THREAD_COUNT",
         help = "The number of CPU threads to allocate to mining",
-        default_value = "1"
+        default_value_t = num_cpus::get() as u64
     )]
     pub threads: u64,
 
+    #[arg(long, help = "Pin each mining thread to its own CPU core")]
+    pub cpu_affinity: bool,
+
     #[arg(
         long,
         short,
@@ -76,10 +202,108 @@ pub struct MineArgs {
         default_value = "5"
     )]
     pub buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Skip submitting a solution unless its difficulty reaches this value"
+    )]
+    pub min_difficulty: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Claim rewards to the signer's wallet whenever they reach this amount"
+    )]
+    pub auto_claim_at: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Stake rewards again after each auto-claim",
+        requires = "auto_claim_at"
+    )]
+    pub auto_compound: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "File to save the best hash to while mining, and resume from after a restart"
+    )]
+    pub state_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Mine with every keypair in this directory at once, splitting the threads between them. Each wallet pays its own fees unless --fee-payer is set."
+    )]
+    pub keypair_dir: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ATTEMPTS",
+        help = "Print a histogram of the best difficulty found per proof attempt every this many attempts, and when mining stops",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub difficulty_histogram: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Submit a single solution and exit, with status 0 once it confirms and 1 if it fails. Hashing stops at the first hash reaching the min difficulty.",
+        conflicts_with = "keypair_dir"
+    )]
+    pub once: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Aim to submit every this many seconds, hashing until a difficulty your measured hashrate is likely to reach in that time, then submitting the best hash. Defaults to submitting at each cutoff.",
+        conflicts_with = "once",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub target_submit_interval: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Reset the epoch in its own transaction before submitting, once it has expired, instead of leaving it to other miners"
+    )]
+    pub auto_reset: bool,
+
+    #[arg(
+        long,
+        value_name = "INDEX",
+        help = "This machine's position in a mining cluster, from 0 to --worker-count minus 1. Give every machine the same --worker-count and its own index, so each searches a separate slice of the nonce space.",
+        default_value = "0"
+    )]
+    pub worker_index: u64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of machines mining the same proof together",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub worker_count: u64,
 }
 
 #[derive(Parser, Debug)]
-pub struct RewardsArgs {}
+pub struct RewardsArgs {
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "URL returning the ORE price as JSON (e.g. CoinGecko's simple price API), to show reward values"
+    )]
+    pub price_source: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "QUOTE",
+        help = "Currency to value rewards in",
+        default_value = "usd",
+        value_parser = ["usd", "sol"]
+    )]
+    pub quote: String,
+}
 
 #[derive(Parser, Debug)]
 pub struct StakeArgs {
@@ -96,6 +320,68 @@ pub struct StakeArgs {
         help = "Token account to send Ore from."
     )]
     pub sender: Option<String>,
+
+    #[arg(
+        long,
+        help = "Simulate the transaction against the RPC and print the results instead of sending it"
+    )]
+    pub simulate_only: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the unsigned transaction to this file for signing offline, instead of sending it. The transaction expires with its blockhash, after about a minute.",
+        conflicts_with = "simulate_only"
+    )]
+    pub dump_unsigned: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Send a transaction signed offline, read from this file, and wait for it to confirm",
+        conflicts_with_all = ["simulate_only", "dump_unsigned"]
+    )]
+    pub submit_signed: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct TransferArgs {
+    #[arg(long, value_name = "WALLET_ADDRESS", help = "Wallet to send ORE to")]
+    pub to: Pubkey,
+
+    #[arg(long, value_name = "AMOUNT", help = "The amount of ORE to send")]
+    pub amount: f64,
+
+    #[arg(
+        long,
+        help = "Create the recipient's ORE token account if it does not exist, paying its rent"
+    )]
+    pub allow_unfunded_recipient: bool,
+
+    #[arg(short, long, help = "Transfer without asking for confirmation")]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        help = "Simulate the transaction against the RPC and print the results instead of sending it"
+    )]
+    pub simulate_only: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the unsigned transaction to this file for signing offline, instead of sending it. The transaction expires with its blockhash, after about a minute.",
+        conflicts_with = "simulate_only"
+    )]
+    pub dump_unsigned: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Send a transaction signed offline, read from this file, and wait for it to confirm",
+        conflicts_with_all = ["simulate_only", "dump_unsigned"]
+    )]
+    pub submit_signed: Option<String>,
 }
 
 #[cfg(feature = "admin")]
@@ -113,3 +399,171 @@ pub struct UpgradeArgs {
     )]
     pub amount: Option<f64>,
 }
+
+pub fn parse_sol(s: &str) -> Result<f64, String> {
+    let amount: f64 = s
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid SOL amount", s))?;
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(format!("`{}` must be a non-negative SOL amount", s));
+    }
+    Ok(amount)
+}
+
+pub fn parse_rpc_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| "RPC headers must look like `Key: Value`".to_string())?;
+    let (name, value) = (name.trim(), value.trim());
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| format!("`{}` is not a valid header name", name))?;
+    HeaderValue::from_str(value)
+        .map_err(|_| format!("The value of header `{}` is invalid", name))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+pub fn parse_multiplier(s: &str) -> Result<f64, String> {
+    let multiplier: f64 = s
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid multiplier", s))?;
+    if !multiplier.is_finite() || multiplier < 1.0 {
+        return Err(format!("`{}` must be a multiplier of at least 1.0", s));
+    }
+    Ok(multiplier)
+}
+
+// Fills in options missing from the command line from ORE_* environment variables, then from
+// the --options file, by appending them to the arguments as flags. Keys in the file are long
+// flag names, e.g. `priority-fee = 5000`. Keys that belong to a different command are ignored,
+// so one file can be shared by every command. Keypair flags are never read from the environment,
+// since variables like ORE_KEYPAIR hold secret keys for `--keypair env:VAR`.
+pub fn resolve_options(command: Command, argv: Vec<OsString>) -> Result<Vec<OsString>, String> {
+    // Let clap report malformed arguments itself, on the real parse
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&argv)
+    else {
+        return Ok(argv);
+    };
+    let file = match matches.get_one::<String>("options") {
+        Some(path) => {
+            let data = fs::read_to_string(path)
+                .map_err(|err| format!("Failed to read options file `{}`: {}", path, err))?;
+            let table = data
+                .parse::<toml::Value>()
+                .map_err(|err| format!("Failed to parse options file `{}`: {}", path, err))?;
+            let toml::Value::Table(table) = table else {
+                return Err(format!("Options file `{}` must be a table", path));
+            };
+            table
+                .into_iter()
+                .map(|(key, value)| (key.replace('_', "-"), value))
+                .collect()
+        }
+        None => toml::value::Table::new(),
+    };
+
+    // Catch typos by rejecting keys that no command accepts
+    let known = |key: &str| {
+        std::iter::once(&command)
+            .chain(command.get_subcommands())
+            .flat_map(|command| command.get_arguments())
+            .any(|arg| arg.get_long() == Some(key))
+    };
+    if let Some(key) = file.keys().find(|key| !known(key)) {
+        return Err(format!("Unknown option `{}` in options file", key));
+    }
+
+    let subcommand = matches
+        .subcommand_name()
+        .and_then(|name| command.find_subcommand(name))
+        .zip(matches.subcommand().map(|(_, matches)| matches));
+    let args = command.get_arguments().map(|arg| (arg, &matches)).chain(
+        subcommand
+            .into_iter()
+            .flat_map(|(command, matches)| command.get_arguments().map(move |arg| (arg, matches))),
+    );
+
+    let mut resolved = argv;
+    for (arg, matches) in args {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        if long == "options" || matches.value_source(arg.get_id().as_str()) == Some(CommandLine) {
+            continue;
+        }
+        let takes_value = arg.get_action().takes_values();
+        let value_name = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map_or("", |name| name.as_str());
+        let var = format!("ORE_{}", long.replace('-', "_").to_uppercase());
+        let env_value = match value_name.starts_with("KEYPAIR") {
+            true => None,
+            false => env::var(&var).ok(),
+        };
+        let values = if let Some(value) = env_value {
+            match takes_value {
+                // Never echo the value, in case it holds key material
+                true if matches!(value_name, "PATH" | "DIR") && !looks_like_path(&value) => {
+                    return Err(format!("{} does not look like a path", var))
+                }
+                true => vec![value],
+                false => match value.as_str() {
+                    "true" | "1" => vec![],
+                    "false" | "0" | "" => continue,
+                    _ => return Err(format!("{} must be true or false", var)),
+                },
+            }
+        } else if let Some(value) = file.get(long) {
+            match option_values(long, value, takes_value)? {
+                Some(values) => values,
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+        if takes_value {
+            for value in values {
+                resolved.push(format!("--{}={}", long, value).into());
+            }
+        } else {
+            resolved.push(format!("--{}", long).into());
+        }
+    }
+    Ok(resolved)
+}
+
+// Whether an environment value could be a file path, rather than e.g. a JSON or base58 key
+fn looks_like_path(value: &str) -> bool {
+    let is_key =
+        value.starts_with('[') || (value.len() >= 64 && bs58::decode(value).into_vec().is_ok());
+    !value.is_empty() && !is_key && !value.chars().any(char::is_control)
+}
+
+// Converts an options file value to flag values. Switches have no values, and None if unset.
+fn option_values(
+    long: &str,
+    value: &toml::Value,
+    takes_value: bool,
+) -> Result<Option<Vec<String>>, String> {
+    let values = match value {
+        toml::Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    let mut resolved = vec![];
+    for value in values {
+        match value {
+            toml::Value::Boolean(true) if !takes_value => {}
+            toml::Value::Boolean(false) if !takes_value => return Ok(None),
+            _ if !takes_value => return Err(format!("`{}` must be true or false", long)),
+            toml::Value::String(value) => resolved.push(value.clone()),
+            toml::Value::Integer(value) => resolved.push(value.to_string()),
+            toml::Value::Float(value) => resolved.push(value.to_string()),
+            toml::Value::Boolean(value) => resolved.push(value.to_string()),
+            _ => return Err(format!("`{}` has an unsupported value", long)),
+        }
+    }
+    Ok(Some(resolved))
+}
@@ -1,17 +1,20 @@
 use std::str::FromStr;
 
-use solana_program::pubkey::Pubkey;
+use serde_json::json;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_sdk::signature::Signer;
+use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::BalanceArgs,
-    utils::{amount_u64_to_string, get_proof_with_authority},
+    program::mint_address,
+    utils::{amount_u64_to_string, exit_on_error, get_proof_with_authority},
     Miner,
 };
 
 impl Miner {
     pub async fn balance(&self, args: BalanceArgs) {
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let address = if let Some(address) = args.address {
             if let Ok(address) = Pubkey::from_str(&address) {
                 address
@@ -23,19 +26,36 @@ impl Miner {
             signer.pubkey()
         };
         let proof = get_proof_with_authority(&self.rpc_client, address).await;
-        let token_account_address = spl_associated_token_account::get_associated_token_address(
-            &address,
-            &ore_api::consts::MINT_ADDRESS,
-        );
-        let token_balance = if let Ok(Some(token_account)) = self
+        let token_account_address =
+            spl_associated_token_account::get_associated_token_address(&address, &mint_address());
+        let token_account = self
             .rpc_client
             .get_token_account(&token_account_address)
             .await
-        {
-            token_account.token_amount.ui_amount_string
-        } else {
-            "0".to_string()
-        };
+            .ok()
+            .flatten();
+
+        // Print machine-readable output
+        if args.output == "json" {
+            let sol_balance = self.rpc_client.get_balance(&address).await.unwrap_or(0);
+            println!(
+                "{}",
+                json!({
+                    "address": address.to_string(),
+                    "sol": lamports_to_sol(sol_balance),
+                    "ore": token_account
+                        .and_then(|token_account| token_account.token_amount.ui_amount)
+                        .unwrap_or(0.0),
+                    "stake": amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
+                    "commitment": self.rpc_client.commitment().commitment,
+                })
+            );
+            return;
+        }
+
+        let token_balance = token_account
+            .map(|token_account| token_account.token_amount.ui_amount_string)
+            .unwrap_or_else(|| "0".to_string());
         println!(
             "Balance: {} ORE\nStake: {} ORE",
             token_balance,
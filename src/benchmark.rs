@@ -1,62 +1,77 @@
 use std::{sync::Arc, time::Instant};
 
-use solana_rpc_client::spinner;
+use drillx::equix;
 
 use crate::{args::BenchmarkArgs, Miner};
 
-const TEST_DURATION: i64 = 30;
-
 impl Miner {
     pub async fn benchmark(&self, args: BenchmarkArgs) {
         // Check num threads
         self.check_num_cores(args.threads);
 
-        // Dispatch job to each thread
-        let challenge = [0; 32];
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        progress_bar.set_message(format!(
-            "Benchmarking. This will take {} sec...",
-            TEST_DURATION
-        ));
-        let handles: Vec<_> = (0..args.threads)
-            .map(|i| {
-                std::thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX.saturating_div(args.threads).saturating_mul(i);
-                        let mut nonce = first_nonce;
-                        loop {
-                            // Create hash
-                            let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
-
-                            // Increment nonce
-                            nonce += 1;
-
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&TEST_DURATION) {
-                                break;
-                            }
-                        }
-
-                        // Return hash count
-                        nonce - first_nonce
-                    }
-                })
-            })
-            .collect();
+        // Hash on every thread for the full duration
+        let duration = args.duration;
+        let progress_bar = Arc::new(self.new_progress_bar());
+        progress_bar.set_message(format!("Benchmarking. This will take {} sec...", duration));
+        let results = measure_hashpower(args.threads, duration);
 
-        // Join handles and return best nonce
+        // Collect per-thread results
         let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
-            }
+        let mut best_difficulty = 0;
+        let mut per_thread = vec![];
+        for (count, difficulty) in results {
+            total_nonces += count;
+            best_difficulty = best_difficulty.max(difficulty);
+            per_thread.push(count);
         }
 
         // Update log
         progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(TEST_DURATION as u64),
+            "Hashpower: {} H/sec (best difficulty: {})",
+            total_nonces.saturating_div(duration),
+            best_difficulty
         ));
+        for (i, count) in per_thread.iter().enumerate() {
+            println!("  Thread {}: {} H/sec", i, count.saturating_div(duration));
+        }
     }
 }
+
+// Hashes a dummy challenge on each thread for the given duration, returning every thread's
+// hash count and best difficulty
+pub fn measure_hashpower(threads: u64, duration: u64) -> Vec<(u64, u32)> {
+    let challenge = [0; 32];
+    let handles: Vec<_> = (0..threads)
+        .map(|i| {
+            std::thread::spawn({
+                let mut memory = equix::SolverMemory::new();
+                move || {
+                    let timer = Instant::now();
+                    let first_nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
+                    let mut nonce = first_nonce;
+                    let mut best_difficulty = 0;
+                    loop {
+                        // Create hash, the same way the miner does
+                        if let Ok(hx) =
+                            drillx::hash_with_memory(&mut memory, &challenge, &nonce.to_le_bytes())
+                        {
+                            best_difficulty = best_difficulty.max(hx.difficulty());
+                        }
+
+                        // Increment nonce
+                        nonce += 1;
+
+                        // Exit if time has elapsed
+                        if timer.elapsed().as_secs().ge(&duration) {
+                            break;
+                        }
+                    }
+
+                    // Return hash count
+                    (nonce - first_nonce, best_difficulty)
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().filter_map(|h| h.join().ok()).collect()
+}
@@ -0,0 +1,100 @@
+use std::{
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcRequest,
+    rpc_response::{Response, RpcBlockhash},
+};
+use solana_sdk::hash::Hash;
+
+use crate::Miner;
+
+const REFRESH_INTERVAL: u64 = 2;
+const MAX_AGE: u64 = 5;
+
+#[derive(Default)]
+pub struct BlockhashCache {
+    latest: RwLock<Option<CachedBlockhash>>,
+}
+
+#[derive(Clone, Copy)]
+struct CachedBlockhash {
+    hash: Hash,
+    slot: u64,
+    fetched_at: Instant,
+}
+
+impl BlockhashCache {
+    fn get(&self) -> Option<(Hash, u64)> {
+        let latest = (*self.latest.read().unwrap())?;
+        if latest.fetched_at.elapsed().as_secs() >= MAX_AGE {
+            return None;
+        }
+        Some((latest.hash, latest.slot))
+    }
+
+    fn clear(&self) {
+        *self.latest.write().unwrap() = None;
+    }
+
+    fn update(&self, hash: Hash, slot: u64) {
+        let mut latest = self.latest.write().unwrap();
+        // Ignore responses from nodes lagging behind the cached view
+        if latest.map_or(true, |cached| slot >= cached.slot) {
+            *latest = Some(CachedBlockhash {
+                hash,
+                slot,
+                fetched_at: Instant::now(),
+            });
+        }
+    }
+}
+
+impl Miner {
+    pub fn start_blockhash_refresher(&self) {
+        let miner = self.clone();
+        tokio::spawn(async move {
+            loop {
+                // Follow RPC rotations, so a failing primary doesn't stall the cache
+                if let Ok((hash, slot)) = fetch_blockhash(&miner.active_client()).await {
+                    miner.blockhash_cache.update(hash, slot);
+                }
+                tokio::time::sleep(Duration::from_secs(REFRESH_INTERVAL)).await;
+            }
+        });
+    }
+
+    // Drops the cached blockhash, so the next lookup fetches one from the RPC
+    pub fn expire_blockhash(&self) {
+        self.blockhash_cache.clear();
+    }
+
+    // Returns the latest blockhash along with the slot it was observed at
+    pub async fn get_latest_blockhash(&self, client: &RpcClient) -> ClientResult<(Hash, u64)> {
+        if let Some(latest) = self.blockhash_cache.get() {
+            return Ok(latest);
+        }
+        let (hash, slot) = fetch_blockhash(client).await?;
+        self.blockhash_cache.update(hash, slot);
+        Ok((hash, slot))
+    }
+}
+
+async fn fetch_blockhash(client: &RpcClient) -> ClientResult<(Hash, u64)> {
+    let response = client
+        .send::<Response<RpcBlockhash>>(
+            RpcRequest::GetLatestBlockhash,
+            json!([client.commitment()]),
+        )
+        .await?;
+    let hash = response.value.blockhash.parse().map_err(|_| ClientError {
+        request: Some(RpcRequest::GetLatestBlockhash),
+        kind: ClientErrorKind::Custom("Failed to parse blockhash".into()),
+    })?;
+    Ok((hash, response.context.slot))
+}
@@ -1,23 +1,70 @@
-use ore_api::{
-    consts::{BUS_ADDRESSES, TOKEN_DECIMALS},
-    state::Bus,
-};
+use ore_api::{consts::EPOCH_DURATION, state::Bus};
 use ore_utils::AccountDeserialize;
+use serde_json::json;
 
-use crate::Miner;
+use crate::{
+    args::BussesArgs,
+    program::bus_addresses,
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_clock, get_config},
+    Miner,
+};
 
 impl Miner {
-    pub async fn busses(&self) {
+    pub async fn busses(&self, args: BussesArgs) {
         let client = self.rpc_client.clone();
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {:} ORE", bus.id, rewards);
-                }
-                Err(_) => {}
+        let accounts = match client.get_multiple_accounts(&bus_addresses()).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                println!("Failed to fetch bus accounts: {}", err);
+                return;
             }
+        };
+        let mut busses: Vec<Bus> = accounts
+            .iter()
+            .flatten()
+            .filter_map(|account| Bus::try_from_bytes(&account.data).ok().copied())
+            .collect();
+        busses.sort_by(|a, b| b.rewards.cmp(&a.rewards).then(a.id.cmp(&b.id)));
+        let total_rewards: u64 = busses.iter().map(|bus| bus.rewards).sum();
+        let config = get_config(&client).await;
+        let clock = get_clock(&client).await;
+        let next_reset = (config.last_reset_at + EPOCH_DURATION - clock.unix_timestamp).max(0);
+
+        // Print machine-readable output
+        if args.output == "json" {
+            println!(
+                "{}",
+                json!({
+                    "busses": busses.iter().map(|bus| json!({
+                        "id": bus.id,
+                        "rewards": amount_u64_to_f64(bus.rewards),
+                        "theoretical_rewards": amount_u64_to_f64(bus.theoretical_rewards),
+                        "top_balance": amount_u64_to_f64(bus.top_balance),
+                    })).collect::<Vec<_>>(),
+                    "total_rewards": amount_u64_to_f64(total_rewards),
+                    "base_reward_rate": amount_u64_to_f64(config.base_reward_rate),
+                    "min_difficulty": config.min_difficulty,
+                    "last_reset_at": config.last_reset_at,
+                    "next_reset_in": next_reset,
+                })
+            );
+            return;
+        }
+
+        for bus in busses.iter() {
+            println!(
+                "Bus {}: {} ORE (top balance {} ORE)",
+                bus.id,
+                amount_u64_to_string(bus.rewards),
+                amount_u64_to_string(bus.top_balance)
+            );
         }
+        println!("Total: {} ORE", amount_u64_to_string(total_rewards));
+        println!(
+            "Base reward rate: {} ORE per hash at difficulty {}",
+            amount_u64_to_string(config.base_reward_rate),
+            config.min_difficulty
+        );
+        println!("Next epoch reset: {}s", next_reset);
     }
 }
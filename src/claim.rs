@@ -1,22 +1,38 @@
 use std::str::FromStr;
 
 use colored::*;
-use ore_api::consts::MINT_ADDRESS;
-use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use solana_program::{native_token::sol_to_lamports, pubkey::Pubkey};
+use solana_sdk::signature::{read_keypair_file, Signature, Signer};
 use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::ClaimArgs,
+    confirm::ConfirmStatus,
     cu_limits::CU_LIMIT_CLAIM,
-    send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm, get_proof_with_authority},
+    program::mint_address,
+    send_request::ComputeBudget,
+    utils::{
+        amount_f64_to_u64, ask_confirm, exit_on_error, get_proof_with_authority, keypair_paths,
+        proof_pubkey,
+    },
     Miner,
 };
 
 impl Miner {
     pub async fn claim(&self, args: ClaimArgs) {
-        let signer = self.signer();
+        if let Some(path) = &args.submit_signed {
+            if self.submit_signed(path).await.is_err() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        if let Some(keypair_dir) = &args.keypair_dir {
+            self.claim_batch(keypair_dir).await;
+            return;
+        }
+        let signer = exit_on_error(self.signer());
         let pubkey = signer.pubkey();
         let proof = get_proof_with_authority(&self.rpc_client, pubkey).await;
         let mut ixs = vec![];
@@ -26,7 +42,7 @@ impl Miner {
                 let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
                 let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
                     &wallet,
-                    &MINT_ADDRESS,
+                    &mint_address(),
                 );
                 if self
                     .rpc_client
@@ -38,7 +54,7 @@ impl Miner {
                         spl_associated_token_account::instruction::create_associated_token_account(
                             &signer.pubkey(),
                             &wallet,
-                            &ore_api::consts::MINT_ADDRESS,
+                            &mint_address(),
                             &spl_token::id(),
                         ),
                     );
@@ -72,20 +88,205 @@ impl Miner {
 
         // Send and confirm
         ixs.push(ore_api::instruction::claim(pubkey, beneficiary, amount));
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+        match self
+            .send_request(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
             .await
-            .ok();
+        {
+            Ok(outcome) => {
+                if let Some(slot) = outcome.landed_slot {
+                    println!(
+                        "Claimed in slot {} after {} attempt(s) at CU limit {}, price {} microlamports/CU",
+                        slot, outcome.attempts, outcome.cu_limit, outcome.priority_fee
+                    );
+                }
+            }
+            Err(_) if self.simulate_only || self.dump_unsigned.is_some() => std::process::exit(1),
+            Err(_) => {}
+        }
+    }
+
+    // Claims all rewards to the signer's wallet once they reach the threshold, without prompting.
+    // Returns the token account and amount claimed, if a claim landed.
+    pub async fn auto_claim(&self, threshold: u64) -> Option<(Pubkey, u64)> {
+        let signer = exit_on_error(self.signer());
+        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
+        if proof.balance < threshold {
+            return None;
+        }
+
+        // Leave the fee payer enough to keep mining
+        let fee_payer = exit_on_error(self.fee_payer());
+        let Ok(balance) = self.rpc_client.get_balance(&fee_payer.pubkey()).await else {
+            return None;
+        };
+        if balance <= sol_to_lamports(self.min_balance) {
+            println!(
+                "{}: Skipping auto-claim, fee payer balance is below {} SOL",
+                "WARNING".bold().yellow(),
+                self.min_balance
+            );
+            return None;
+        }
+
+        let beneficiary = self.initialize_ata().await;
+        let ix = ore_api::instruction::claim(signer.pubkey(), beneficiary, proof.balance);
+        match self
+            .send_request(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+            .await
+        {
+            Ok(outcome) => {
+                println!(
+                    "Auto-claimed {} ORE: {}",
+                    amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
+                    outcome.signature
+                );
+                Some((beneficiary, proof.balance))
+            }
+            Err(err) => {
+                println!("{}: Auto-claim failed: {}", "WARNING".bold().yellow(), err);
+                None
+            }
+        }
+    }
+
+    async fn claim_batch(&self, keypair_dir: &str) {
+        // Load every keypair in the directory
+        let paths = match keypair_paths(keypair_dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                println!(
+                    "{}: Failed to read {}: {}",
+                    "ERROR".bold().red(),
+                    keypair_dir,
+                    err
+                );
+                return;
+            }
+        };
+        let mut wallets = vec![];
+        for path in paths {
+            let Ok(keypair) = read_keypair_file(&path) else {
+                println!("Skipping {}: not a keypair file", path.display());
+                continue;
+            };
+            let proof = match self
+                .rpc_client
+                .get_account_data(&proof_pubkey(keypair.pubkey()))
+                .await
+            {
+                Ok(data) => Proof::try_from_bytes(&data).ok().copied(),
+                Err(_) => None,
+            };
+            match proof {
+                Some(proof) if proof.balance > 0 => wallets.push((keypair, proof.balance)),
+                Some(_) => println!("Skipping {}: no rewards to claim", keypair.pubkey()),
+                None => println!("Skipping {}: no miner account", keypair.pubkey()),
+            }
+        }
+        if wallets.is_empty() {
+            println!("No rewards to claim");
+            return;
+        }
+
+        // Confirm user wants to claim
+        let total: u64 = wallets.iter().map(|(_, amount)| amount).sum();
+        if !ask_confirm(
+            format!(
+                "\nYou are about to claim {} from {} wallets.\n\nAre you sure you want to continue? [Y/n]",
+                format!(
+                    "{} ORE",
+                    amount_to_ui_amount(total, ore_api::consts::TOKEN_DECIMALS)
+                )
+                .bold(),
+                wallets.len()
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        // Claim for each wallet in turn, continuing past failures
+        let mut results = vec![];
+        for (keypair, amount) in wallets {
+            let beneficiary = spl_associated_token_account::get_associated_token_address(
+                &keypair.pubkey(),
+                &mint_address(),
+            );
+            let mut ixs = vec![];
+            if !matches!(
+                self.rpc_client.get_token_account(&beneficiary).await,
+                Ok(Some(_))
+            ) {
+                ixs.push(
+                    spl_associated_token_account::instruction::create_associated_token_account(
+                        &keypair.pubkey(),
+                        &keypair.pubkey(),
+                        &mint_address(),
+                        &spl_token::id(),
+                    ),
+                );
+            }
+            ixs.push(ore_api::instruction::claim(
+                keypair.pubkey(),
+                beneficiary,
+                amount,
+            ));
+            let result = self
+                .send_request_with_keys(
+                    &ixs,
+                    ComputeBudget::Fixed(CU_LIMIT_CLAIM),
+                    true,
+                    &keypair,
+                    &keypair,
+                )
+                .await
+                .map(|outcome| outcome.signature);
+            results.push((keypair.pubkey(), result, amount));
+        }
+
+        // Confirm every submitted claim together
+        let signatures: Vec<Signature> = results
+            .iter()
+            .filter_map(|(_, result, _)| result.as_ref().ok().copied())
+            .collect();
+        let statuses = self.confirm_signatures(&signatures).await;
+
+        // Print summary
+        println!(
+            "\n{:<44}  {:<88}  {:<14}  Status",
+            "Wallet", "Signature", "Claimed (ORE)"
+        );
+        for (wallet, result, amount) in results {
+            let (signature, status) = match result {
+                Ok(sig) => (
+                    sig.to_string(),
+                    match statuses.get(&sig) {
+                        Some(ConfirmStatus::Confirmed(_)) => "confirmed".to_string(),
+                        Some(ConfirmStatus::Failed(err)) => format!("failed: {}", err),
+                        _ => "unconfirmed".to_string(),
+                    },
+                ),
+                Err(err) => (String::new(), format!("failed: {}", err)),
+            };
+            println!(
+                "{:<44}  {:<88}  {:<14}  {}",
+                wallet.to_string(),
+                signature,
+                amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS),
+                status
+            );
+        }
     }
 
-    async fn initialize_ata(&self) -> Pubkey {
+    pub async fn initialize_ata(&self) -> Pubkey {
         // Initialize client.
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let client = self.rpc_client.clone();
 
         // Build instructions.
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &mint_address(),
         );
 
         // Check if ata already exists
@@ -96,10 +297,10 @@ impl Miner {
         let ix = spl_associated_token_account::instruction::create_associated_token_account(
             &signer.pubkey(),
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &mint_address(),
             &spl_token::id(),
         );
-        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+        self.send_request(&[ix], ComputeBudget::Dynamic, false)
             .await
             .ok();
 
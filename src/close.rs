@@ -1,22 +1,33 @@
 use colored::*;
+use solana_program::native_token::lamports_to_sol;
 use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
-    args::ClaimArgs,
-    send_and_confirm::ComputeBudget,
-    utils::{ask_confirm, get_proof_with_authority},
+    args::CloseArgs,
+    send_request::ComputeBudget,
+    utils::{ask_confirm, exit_on_error, get_proof_with_authority, proof_pubkey},
     Miner,
 };
 
 impl Miner {
-    pub async fn close(&self) {
+    pub async fn close(&self, args: CloseArgs) {
         // Confirm proof exists
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
 
+        // Refuse to close over unclaimed rewards unless forced
+        if proof.balance.gt(&0) && !args.force {
+            println!(
+                "{}: This account still holds {} ORE. Claim it first, or pass --force to claim and close together.",
+                "ERROR".bold().red(),
+                amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS)
+            );
+            return;
+        }
+
         // Confirm the user wants to close.
-        if !ask_confirm(
+        if !args.yes && !ask_confirm(
             format!("{} You have {} ORE staked in this account.\nAre you sure you want to {}close this account? [Y/n]", 
                 "WARNING".yellow(),
                 amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
@@ -26,19 +37,30 @@ impl Miner {
             return;
         }
 
-        // Claim stake
+        // Claim stake in the same transaction, so the account is never closed over rewards
+        let mut ixs = vec![];
         if proof.balance.gt(&0) {
-            self.claim(ClaimArgs {
-                amount: None,
-                to: None,
-            })
-            .await;
+            let beneficiary = self.initialize_ata().await;
+            ixs.push(ore_api::instruction::claim(
+                signer.pubkey(),
+                beneficiary,
+                proof.balance,
+            ));
         }
 
         // Submit close transaction
-        let ix = ore_api::instruction::close(signer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+        let rent = self
+            .rpc_client
+            .get_balance(&proof_pubkey(signer.pubkey()))
             .await
-            .ok();
+            .unwrap_or(0);
+        ixs.push(ore_api::instruction::close(signer.pubkey()));
+        if self
+            .send_request(&ixs, ComputeBudget::Dynamic, false)
+            .await
+            .is_ok()
+        {
+            println!("Reclaimed {} SOL", lamports_to_sol(rent));
+        }
     }
 }
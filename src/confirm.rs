@@ -0,0 +1,61 @@
+use std::{collections::HashMap, time::Duration};
+
+use solana_sdk::{signature::Signature, transaction::TransactionError};
+
+use crate::Miner;
+
+// Most signatures getSignatureStatuses accepts per request
+const MAX_SIGNATURES_PER_REQUEST: usize = 256;
+
+/// Final state of a signature after batch confirmation.
+pub enum ConfirmStatus {
+    /// Reached the required commitment in the given slot
+    Confirmed(u64),
+    /// Landed, but the transaction failed on chain
+    Failed(TransactionError),
+    /// Not seen at the required commitment before retries ran out
+    Unconfirmed,
+}
+
+impl Miner {
+    // Confirms many signatures at once, polling only the ones still pending on each round
+    pub async fn confirm_signatures(
+        &self,
+        signatures: &[Signature],
+    ) -> HashMap<Signature, ConfirmStatus> {
+        let mut results: HashMap<Signature, ConfirmStatus> = signatures
+            .iter()
+            .map(|sig| (*sig, ConfirmStatus::Unconfirmed))
+            .collect();
+        let mut pending = signatures.to_vec();
+        for _ in 0..self.confirm_retries {
+            if pending.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(self.confirm_delay_ms)).await;
+            let mut still_pending = vec![];
+            for chunk in pending.chunks(MAX_SIGNATURES_PER_REQUEST) {
+                let Ok(statuses) = self.rpc_client.get_signature_statuses(chunk).await else {
+                    // Try the whole chunk again next round
+                    still_pending.extend_from_slice(chunk);
+                    continue;
+                };
+                for (sig, status) in chunk.iter().zip(statuses.value) {
+                    let Some(status) = status else {
+                        still_pending.push(*sig);
+                        continue;
+                    };
+                    if let Some(err) = status.err {
+                        results.insert(*sig, ConfirmStatus::Failed(err));
+                    } else if self.reached_commitment(&status.confirmation_status) {
+                        results.insert(*sig, ConfirmStatus::Confirmed(status.slot));
+                    } else {
+                        still_pending.push(*sig);
+                    }
+                }
+            }
+            pending = still_pending;
+        }
+        results
+    }
+}
@@ -0,0 +1,120 @@
+use colored::*;
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::signature::Signer;
+
+use crate::{
+    utils::{load_keypair, proof_pubkey, redact_url, redact_url_in},
+    Miner,
+};
+
+impl Miner {
+    pub async fn doctor(&self) {
+        let mut healthy = true;
+
+        // RPC
+        let rpc_url = self.rpc_client.url();
+        match self.rpc_client.get_slot().await {
+            Ok(slot) => pass(&format!(
+                "RPC {} is reachable (slot {})",
+                redact_url(&rpc_url),
+                slot
+            )),
+            Err(err) => {
+                fail(&format!(
+                    "RPC {} is unreachable: {}",
+                    redact_url(&rpc_url),
+                    redact_url_in(&err.to_string(), &rpc_url)
+                ));
+                healthy = false;
+            }
+        }
+        for name in &self.rpc_header_names {
+            println!("  RPC header {}: <redacted>", name);
+        }
+
+        // Keypairs
+        let signer = match load_keypair(self.keypair_filepath.as_deref().unwrap_or_default()) {
+            Ok(signer) => {
+                pass(&format!("Signer keypair loaded ({})", signer.pubkey()));
+                Some(signer)
+            }
+            Err(err) => {
+                fail(&format!("Signer keypair: {}", err));
+                healthy = false;
+                None
+            }
+        };
+        match load_keypair(self.fee_payer_filepath.as_deref().unwrap_or_default()) {
+            Ok(fee_payer) => match self.rpc_client.get_balance(&fee_payer.pubkey()).await {
+                Ok(balance) if balance > sol_to_lamports(self.min_balance) => pass(&format!(
+                    "Fee payer balance is {} SOL",
+                    lamports_to_sol(balance)
+                )),
+                Ok(balance) => {
+                    fail(&format!(
+                        "Fee payer balance is {} SOL, below the minimum of {} SOL",
+                        lamports_to_sol(balance),
+                        self.min_balance
+                    ));
+                    healthy = false;
+                }
+                Err(err) => {
+                    fail(&format!("Failed to fetch fee payer balance: {}", err));
+                    healthy = false;
+                }
+            },
+            Err(err) => {
+                fail(&format!("Fee payer keypair: {}", err));
+                healthy = false;
+            }
+        }
+
+        // Proof account. Mining opens one if needed, so this is only a warning.
+        if let Some(signer) = &signer {
+            match self
+                .rpc_client
+                .get_account_data(&proof_pubkey(signer.pubkey()))
+                .await
+            {
+                Ok(data) if Proof::try_from_bytes(&data).is_ok() => {
+                    pass("Proof account is initialized")
+                }
+                _ => warn("Proof account is not initialized. Mining will open one."),
+            }
+        }
+
+        // Dynamic fee provider
+        if let Some(url) = &self.dynamic_fee_url {
+            match self.dynamic_fee(&[]).await {
+                Ok(fee) => pass(&format!(
+                    "Fee provider {} responded ({} microlamports)",
+                    redact_url(url),
+                    fee
+                )),
+                Err(err) => warn(&format!(
+                    "Fee provider {} failed: {}. The static priority fee will be used.",
+                    redact_url(url),
+                    redact_url_in(&err, url)
+                )),
+            }
+        }
+
+        if !healthy {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn pass(message: &str) {
+    println!("{} {}", "PASS".bold().green(), message);
+}
+
+fn warn(message: &str) {
+    println!("{} {}", "WARN".bold().yellow(), message);
+}
+
+fn fail(message: &str) {
+    println!("{} {}", "FAIL".bold().red(), message);
+}
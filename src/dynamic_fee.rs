@@ -1,32 +1,52 @@
-use crate::Miner;
+use std::time::Duration;
+
+use crate::{
+    program::{bus_addresses, program_id},
+    Miner,
+};
 
-use ore_api::consts::BUS_ADDRESSES;
-use reqwest::Client;
 use serde_json::{json, Value};
+use solana_sdk::{instruction::Instruction, transaction::Transaction};
+
+const DYNAMIC_FEE_TIMEOUT: u64 = 5;
 
 impl Miner {
-    pub async fn dynamic_fee(&self) -> u64 {
-        let ore_addresses: Vec<String> =
-            std::iter::once("oreV2ZymfyeXgNgBdqMkumTqqAprVqgBWQfoYkrtKWQ".to_string())
-                .chain(BUS_ADDRESSES.iter().map(|pubkey| pubkey.to_string()))
+    pub async fn dynamic_fee(&self, ixs: &[Instruction]) -> Result<u64, String> {
+        // Scope fee lookups to the ORE accounts this transaction write-locks
+        let mut ore_addresses: Vec<String> = vec![];
+        for ix in ixs.iter().filter(|ix| ix.program_id == program_id()) {
+            for account in ix.accounts.iter().filter(|account| account.is_writable) {
+                let address = account.pubkey.to_string();
+                if !ore_addresses.contains(&address) {
+                    ore_addresses.push(address);
+                }
+            }
+        }
+        if ore_addresses.is_empty() {
+            ore_addresses = std::iter::once(program_id().to_string())
+                .chain(bus_addresses().iter().map(|pubkey| pubkey.to_string()))
                 .collect();
+        }
 
         match &self.dynamic_fee_strategy {
-            None => self.priority_fee.unwrap_or(0),
+            None => Ok(self.priority_fee.unwrap_or(0)),
             Some(strategy) => {
-                let client = Client::new();
-
                 let body = match strategy.as_str() {
                     "helius" => {
+                        let tx = Transaction::new_with_payer(ixs, None);
+                        let serialized_tx = bincode::serialize(&tx)
+                            .map_err(|err| format!("Failed to serialize transaction: {}", err))?;
+                        let options = match &self.dynamic_fee_level {
+                            Some(level) => json!({ "priorityLevel": helius_priority_level(level) }),
+                            None => json!({ "recommended": true }),
+                        };
                         json!({
                             "jsonrpc": "2.0",
                             "id": "priority-fee-estimate",
                             "method": "getPriorityFeeEstimate",
                             "params": [{
-                                "accountKeys": ore_addresses,
-                                "options": {
-                                    "recommended": true
-                                }
+                                "transaction": bs58::encode(serialized_tx).into_string(),
+                                "options": options
                             }]
                         })
                     }
@@ -35,26 +55,26 @@ impl Miner {
                             "jsonrpc": "2.0",
                             "id": "priority-fee-estimate",
                             "method": "getRecentPrioritizationFees",
-                            "params": [
-                                ore_addresses,
-                                {
-                                    "percentile": 5000,
-                                }
-                            ]
+                            "params": [ore_addresses]
                         })
                     }
-                    _ => return self.priority_fee.unwrap_or(0),
+                    _ => return Ok(self.priority_fee.unwrap_or(0)),
                 };
 
-                let response: Value = client
+                let response: Value = self
+                    .http_client
                     .post(self.dynamic_fee_url.as_ref().unwrap())
+                    .timeout(Duration::from_secs(DYNAMIC_FEE_TIMEOUT))
                     .json(&body)
                     .send()
                     .await
-                    .unwrap()
+                    .map_err(|err| format!("Failed to fetch priority fee: {}", err))?
                     .json()
                     .await
-                    .unwrap();
+                    .map_err(|err| format!("Failed to read priority fee response: {}", err))?;
+                if let Some(err) = response.get("error") {
+                    return Err(format!("Priority fee provider returned an error: {}", err));
+                }
 
                 let calculated_fee = match strategy.as_str() {
                     "helius" => response["result"]["priorityFeeEstimate"]
@@ -62,26 +82,146 @@ impl Miner {
                         .map(|fee| fee as u64)
                         .ok_or_else(|| {
                             format!("Failed to parse priority fee. Response: {:?}", response)
-                        })
-                        .unwrap(),
-                    "triton" => response["result"]
-                        .as_array()
-                        .and_then(|arr| arr.last())
-                        .and_then(|last| last["prioritizationFee"].as_u64())
-                        .ok_or_else(|| {
-                            format!("Failed to parse priority fee. Response: {:?}", response)
-                        })
-                        .unwrap(),
-                    _ => return self.priority_fee.unwrap_or(0),
+                        })?,
+                    // Take the percentile over the recent slots here, rather than rely on
+                    // providers that accept a percentile param
+                    "triton" => {
+                        let fees: Vec<u64> = response["result"]
+                            .as_array()
+                            .ok_or_else(|| {
+                                format!("Failed to parse priority fee. Response: {:?}", response)
+                            })?
+                            .iter()
+                            .filter_map(|fee| fee["prioritizationFee"].as_u64())
+                            .collect();
+                        match percentile_fee(fees, self.dynamic_fee_percentile) {
+                            Some(fee) => fee,
+                            None => return Ok(self.priority_fee.unwrap_or(0)),
+                        }
+                    }
+                    _ => return Ok(self.priority_fee.unwrap_or(0)),
                 };
 
                 // Check if the calculated fee is higher than self.dynamic_fee_max
                 if let Some(max_fee) = self.dynamic_fee_max {
-                    calculated_fee.min(max_fee)
+                    Ok(calculated_fee.min(max_fee))
                 } else {
-                    calculated_fee
+                    Ok(calculated_fee)
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Picks the nearest-rank percentile of the fees, or None when there are none
+fn percentile_fee(mut fees: Vec<u64>, percentile: u8) -> Option<u64> {
+    if fees.is_empty() {
+        return None;
+    }
+    fees.sort_unstable();
+    let rank = (fees.len() * percentile as usize).div_ceil(100);
+    Some(fees[rank.saturating_sub(1)])
+}
+
+fn helius_priority_level(level: &str) -> &'static str {
+    match level {
+        "min" => "Min",
+        "low" => "Low",
+        "high" => "High",
+        "veryHigh" => "VeryHigh",
+        _ => "Medium",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use solana_sdk::{pubkey::Pubkey, system_instruction};
+
+    use super::*;
+    use crate::test_utils::{scripted_client, serve_json, test_miner};
+
+    fn test_ixs() -> Vec<Instruction> {
+        vec![system_instruction::transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        )]
+    }
+
+    #[tokio::test]
+    async fn parses_helius_estimate() {
+        let (url, request) = serve_json(json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "result": { "priorityFeeEstimate": 12345.0 },
+        }))
+        .await;
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.dynamic_fee_url = Some(url);
+        miner.dynamic_fee_strategy = Some("helius".into());
+        miner.dynamic_fee_level = Some("high".into());
+        assert_eq!(miner.dynamic_fee(&test_ixs()).await, Ok(12345));
+        let request = request.await.unwrap();
+        assert_eq!(request["method"], "getPriorityFeeEstimate");
+        assert_eq!(request["params"][0]["options"]["priorityLevel"], "High");
+    }
+
+    #[tokio::test]
+    async fn reports_unparseable_helius_response() {
+        let (url, _) = serve_json(json!({ "jsonrpc": "2.0", "result": {} })).await;
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.dynamic_fee_url = Some(url);
+        miner.dynamic_fee_strategy = Some("helius".into());
+        let err = miner.dynamic_fee(&test_ixs()).await.unwrap_err();
+        assert!(err.starts_with("Failed to parse priority fee"), "{}", err);
+    }
+
+    #[test]
+    fn picks_nearest_rank_percentile() {
+        let fees = vec![500, 100, 400, 200, 300];
+        assert_eq!(percentile_fee(fees.clone(), 1), Some(100));
+        assert_eq!(percentile_fee(fees.clone(), 50), Some(300));
+        assert_eq!(percentile_fee(fees.clone(), 75), Some(400));
+        assert_eq!(percentile_fee(fees, 100), Some(500));
+        assert_eq!(percentile_fee(vec![], 75), None);
+    }
+
+    #[tokio::test]
+    async fn takes_triton_fee_percentile_across_slots() {
+        let (url, request) = serve_json(json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "result": [
+                { "slot": 1, "prioritizationFee": 500 },
+                { "slot": 2, "prioritizationFee": 100 },
+                { "slot": 3, "prioritizationFee": 400 },
+                { "slot": 4, "prioritizationFee": 200 },
+            ],
+        }))
+        .await;
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.dynamic_fee_url = Some(url);
+        miner.dynamic_fee_strategy = Some("triton".into());
+        miner.dynamic_fee_percentile = 75;
+        assert_eq!(miner.dynamic_fee(&test_ixs()).await, Ok(400));
+        let request = request.await.unwrap();
+        assert!(request["params"].get(1).is_none(), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_static_fee_without_triton_fees() {
+        let (url, _) = serve_json(json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "result": [],
+        }))
+        .await;
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.priority_fee = Some(5_000);
+        miner.dynamic_fee_url = Some(url);
+        miner.dynamic_fee_strategy = Some("triton".into());
+        assert_eq!(miner.dynamic_fee(&test_ixs()).await, Ok(5_000));
+    }
+}
@@ -0,0 +1,116 @@
+use colored::*;
+use ore_api::{consts::ONE_MINUTE, state::Bus};
+use ore_utils::AccountDeserialize;
+
+use crate::{
+    args::EstimateArgs,
+    benchmark::measure_hashpower,
+    program::bus_addresses,
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_config},
+    Miner,
+};
+
+// Difficulties above this many levels past the minimum are too unlikely to matter
+const MAX_EXTRA_DIFFICULTY: u32 = 32;
+
+impl Miner {
+    pub async fn estimate(&self, args: EstimateArgs) {
+        // Measure the hashrate, unless one was given
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => {
+                self.check_num_cores(args.threads);
+                let progress_bar = self.new_progress_bar();
+                progress_bar.set_message(format!(
+                    "Measuring hashpower. This will take {} sec...",
+                    args.duration
+                ));
+                let total_nonces: u64 = measure_hashpower(args.threads, args.duration)
+                    .iter()
+                    .map(|(count, _)| count)
+                    .sum();
+                progress_bar.finish_and_clear();
+                total_nonces.saturating_div(args.duration)
+            }
+        };
+
+        // Fetch the reward parameters
+        let config = get_config(&self.rpc_client).await;
+        let accounts = match self
+            .rpc_client
+            .get_multiple_accounts(&bus_addresses())
+            .await
+        {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                println!(
+                    "{}: Failed to fetch bus accounts: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+                return;
+            }
+        };
+        let max_bus_rewards = accounts
+            .iter()
+            .flatten()
+            .filter_map(|account| Bus::try_from_bytes(&account.data).ok())
+            .map(|bus| bus.rewards)
+            .max()
+            .unwrap_or(0);
+
+        // Each submission pays base_reward_rate * 2^(difficulty - min_difficulty), capped by
+        // what is left in the bus. With n hashes, P(best difficulty >= d) = 1 - e^(-n / 2^d).
+        let window = (ONE_MINUTE as u64).saturating_sub(args.buffer_time);
+        let hashes = hashrate.saturating_mul(window) as f64;
+        let p_at_least = |difficulty: u32| 1.0 - (-hashes / 2f64.powi(difficulty as i32)).exp();
+        let min_difficulty = config.min_difficulty as u32;
+        let mut expected_reward = 0.0;
+        for i in 0..MAX_EXTRA_DIFFICULTY {
+            let difficulty = min_difficulty + i;
+            let p = p_at_least(difficulty) - p_at_least(difficulty + 1);
+            let reward = config
+                .base_reward_rate
+                .saturating_mul(2u64.saturating_pow(i))
+                .min(max_bus_rewards);
+            expected_reward += p * reward as f64;
+        }
+        let typical_difficulty = hashes.max(1.0).log2().floor() as u32;
+        let submissions_per_hour = 3600.0 / ONE_MINUTE as f64;
+        let per_submission = amount_u64_to_f64(expected_reward as u64);
+
+        println!("{}", "Assumptions".bold());
+        println!("  Hashrate: {} H/sec", hashrate);
+        println!(
+            "  Hashing window: {} sec per {} sec submission",
+            window, ONE_MINUTE
+        );
+        println!("  Min difficulty: {}", config.min_difficulty);
+        println!(
+            "  Base reward rate: {} ORE",
+            amount_u64_to_string(config.base_reward_rate)
+        );
+        println!(
+            "  Reward cap: {} ORE (richest bus)",
+            amount_u64_to_string(max_bus_rewards)
+        );
+        println!("  Excludes stake multipliers and transaction fees");
+        println!("{}", "Estimate".bold());
+        println!("  Typical difficulty: {}", typical_difficulty);
+        if p_at_least(min_difficulty) < 0.5 {
+            println!(
+                "  {}: Most submissions will not reach the min difficulty",
+                "WARNING".bold().yellow()
+            );
+        }
+        println!("  Per submission: {:.11} ORE", per_submission);
+        println!(
+            "  Per hour: {:.11} ORE",
+            per_submission * submissions_per_hour
+        );
+        println!(
+            "  Per day: {:.11} ORE",
+            per_submission * submissions_per_hour * 24.0
+        );
+    }
+}
@@ -1,22 +1,27 @@
-use ore_api::consts::TREASURY_ADDRESS;
 use solana_sdk::{signature::Signer, transaction::Transaction};
 
-use crate::Miner;
+use crate::{program::treasury_address, utils::exit_on_error, Miner};
 
 impl Miner {
     pub async fn initialize(&self) {
         // Return early if program is already initialized
-        if self.rpc_client.get_account(&TREASURY_ADDRESS).await.is_ok() {
+        if self
+            .rpc_client
+            .get_account(&treasury_address())
+            .await
+            .is_ok()
+        {
             return;
         }
 
         // Submit initialize tx
         let blockhash = self.rpc_client.get_latest_blockhash().await.unwrap();
-        let ix = ore_api::instruction::initialize(self.signer().pubkey());
+        let signer = exit_on_error(self.signer());
+        let ix = ore_api::instruction::initialize(signer.pubkey());
         let tx = Transaction::new_signed_with_payer(
             &[ix],
-            Some(&self.signer().pubkey()),
-            &[&self.signer()],
+            Some(&signer.pubkey()),
+            &[&signer],
             blockhash,
         );
         let res = self.rpc_client.send_and_confirm_transaction(&tx).await;
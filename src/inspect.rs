@@ -0,0 +1,98 @@
+use std::str::FromStr;
+
+use colored::*;
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use serde_json::json;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    args::InspectArgs,
+    utils::{amount_u64_to_string, exit_on_error, proof_pubkey},
+    Miner,
+};
+
+impl Miner {
+    pub async fn inspect(&self, args: InspectArgs) {
+        let authority = match args.address {
+            Some(address) => match Pubkey::from_str(&address) {
+                Ok(address) => address,
+                Err(_) => {
+                    println!("Invalid address: {:?}", address);
+                    return;
+                }
+            },
+            None => exit_on_error(self.signer()).pubkey(),
+        };
+
+        // Fetch and decode the proof account
+        let proof_address = proof_pubkey(authority);
+        let data = match self.rpc_client.get_account_data(&proof_address).await {
+            Ok(data) => data,
+            Err(err) => {
+                println!(
+                    "{}: No proof account found for {}: {}",
+                    "ERROR".bold().red(),
+                    authority,
+                    err
+                );
+                return;
+            }
+        };
+        let Ok(proof) = Proof::try_from_bytes(&data) else {
+            println!(
+                "{}: Account {} is not a proof account",
+                "ERROR".bold().red(),
+                proof_address
+            );
+            return;
+        };
+
+        if args.json {
+            println!(
+                "{}",
+                json!({
+                    "address": proof_address.to_string(),
+                    "authority": proof.authority.to_string(),
+                    "miner": proof.miner.to_string(),
+                    "balance": proof.balance,
+                    "challenge": bs58::encode(proof.challenge).into_string(),
+                    "last_hash": bs58::encode(proof.last_hash).into_string(),
+                    "last_hash_at": proof.last_hash_at,
+                    "last_stake_at": proof.last_stake_at,
+                    "total_hashes": proof.total_hashes,
+                    "total_rewards": proof.total_rewards,
+                })
+            );
+            return;
+        }
+
+        println!("{}: {}", "Address".bold(), proof_address);
+        println!("{}: {}", "Authority".bold(), proof.authority);
+        println!("{}: {}", "Miner".bold(), proof.miner);
+        println!(
+            "{}: {} ORE",
+            "Balance".bold(),
+            amount_u64_to_string(proof.balance)
+        );
+        println!(
+            "{}: {}",
+            "Challenge".bold(),
+            bs58::encode(proof.challenge).into_string()
+        );
+        println!(
+            "{}: {}",
+            "Last hash".bold(),
+            bs58::encode(proof.last_hash).into_string()
+        );
+        println!("{}: {}", "Last hash at".bold(), proof.last_hash_at);
+        println!("{}: {}", "Last stake at".bold(), proof.last_stake_at);
+        println!("{}: {}", "Total hashes".bold(), proof.total_hashes);
+        println!(
+            "{}: {} ORE",
+            "Total rewards".bold(),
+            amount_u64_to_string(proof.total_rewards)
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::Instant;
+
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde_json::{json, Value};
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use crate::{utils::parse_retry_after, Miner};
+
+/// Jito's published mainnet tip accounts.
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+impl Miner {
+    pub async fn send_bundle(&self, tx: &VersionedTransaction) -> ClientResult<Signature> {
+        let serialized_tx = bincode::serialize(tx).map_err(|err| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(format!("Failed to serialize transaction: {}", err)),
+        })?;
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendBundle",
+            "params": [[bs58::encode(serialized_tx).into_string()]]
+        });
+
+        let response = self
+            .http_client
+            .post(&self.jito_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(jito_error)?;
+
+        // Hold off the next submission for as long as the block engine asks
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            if let Some(retry_after) = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after)
+            {
+                *self.retry_after.lock().unwrap() = Some(Instant::now() + retry_after);
+            }
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom("Jito bundle rate limited (429)".into()),
+            });
+        }
+        let response: Value = response.json().await.map_err(jito_error)?;
+        if let Some(err) = response.get("error") {
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("Jito bundle rejected: {}", err)),
+            });
+        }
+
+        // A single-transaction bundle lands under the transaction's own signature
+        Ok(tx.signatures[0])
+    }
+}
+
+fn jito_error(err: reqwest::Error) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(format!("Jito bundle request failed: {}", err)),
+    }
+}
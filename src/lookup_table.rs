@@ -0,0 +1,25 @@
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_program::address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount};
+
+use crate::Miner;
+
+impl Miner {
+    // Fetches the configured address lookup table, if any, for compiling v0 transactions
+    pub async fn lookup_tables(&self) -> ClientResult<Vec<AddressLookupTableAccount>> {
+        let Some(key) = self.lookup_table else {
+            return Ok(vec![]);
+        };
+        let data = self.rpc_client.get_account_data(&key).await?;
+        let table = AddressLookupTable::deserialize(&data).map_err(|err| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(format!(
+                "Failed to parse address lookup table {}: {}",
+                key, err
+            )),
+        })?;
+        Ok(vec![AddressLookupTableAccount {
+            key,
+            addresses: table.addresses.to_vec(),
+        }])
+    }
+}
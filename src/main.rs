@@ -1,41 +1,161 @@
 mod args;
 mod balance;
 mod benchmark;
+mod blockhash;
 mod busses;
 mod claim;
 mod close;
 mod config;
+mod confirm;
 mod cu_limits;
+mod doctor;
+mod dynamic_fee;
+mod estimate;
 #[cfg(feature = "admin")]
 mod initialize;
+mod inspect;
+mod jito;
+mod lookup_table;
+mod metrics;
 mod mine;
+mod nonce;
 mod open;
+mod program;
+mod progress;
+mod refill;
 mod rewards;
-mod send_and_confirm;
+mod rpc;
 mod send_request;
 mod stake;
+mod state;
+mod stats;
+mod telemetry;
+#[cfg(test)]
+mod test_utils;
+mod tpu;
+mod transfer;
 mod upgrade;
 mod utils;
-mod dynamic_fee;
+mod wallets;
 
-use std::sync::Arc;
+use std::{
+    io::IsTerminal,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use args::*;
-use clap::{command, Parser, Subcommand};
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{
-    commitment_config::CommitmentConfig,
-    signature::{read_keypair_file, Keypair},
+use blockhash::BlockhashCache;
+use clap::{command, error::ErrorKind, CommandFactory, Parser, Subcommand};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use jito::JITO_TIP_ACCOUNTS;
+use metrics::Metrics;
+use progress::plain_draw_target;
+use send_request::{
+    CONFIRM_DELAY, CONFIRM_RETRIES, CU_MARGIN_PERCENT, GATEWAY_DELAY, GATEWAY_RETRIES,
+    MAX_COMPUTE_UNIT_LIMIT, RPC_TIMEOUT, SIMULATION_RETRIES,
 };
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client::spinner;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair};
+use stats::StatsCsv;
+use tpu::QuicTpuClient;
+use utils::load_keypair;
 
+// Clones share the clients, caches, and rate limiting state, which are all behind an Arc
+#[derive(Clone)]
 struct Miner {
     pub keypair_filepath: Option<String>,
+    pub rpc_header_names: Vec<String>,
+    pub http_client: reqwest::Client,
     pub priority_fee: Option<u64>,
+    pub mine_priority_fee: Option<u64>,
+    pub claim_priority_fee: Option<u64>,
+    pub stake_priority_fee: Option<u64>,
     pub dynamic_fee_url: Option<String>,
     pub dynamic_fee_strategy: Option<String>,
+    pub dynamic_fee_level: Option<String>,
+    pub dynamic_fee_percentile: u8,
     pub dynamic_fee_max: Option<u64>,
+    pub dynamic_fee_requeries: usize,
+    pub priority_fee_max: Option<u64>,
+    pub priority_fee_min: Option<u64>,
+    pub max_priority_fee_per_tx_sol: Option<f64>,
+    pub high_fee_threshold_sol: f64,
+    pub allow_high_fee: bool,
+    pub cu_limit: Option<u32>,
+    pub cu_margin_percent: u8,
+    pub simulate_compute_then_send: bool,
+    pub simulation_retries: usize,
+    pub fee_escalation_attempts: usize,
+    pub fee_escalation_multiplier: f64,
     pub rpc_client: Arc<RpcClient>,
+    pub blockhash_cache: Arc<BlockhashCache>,
+    pub rpc_fallbacks: Vec<Arc<RpcClient>>,
+    pub rpc_fallback_threshold: usize,
+    pub active_rpc: Arc<AtomicUsize>,
+    pub spam_rpcs: bool,
+    pub ws_url: Option<String>,
     pub fee_payer_filepath: Option<String>,
+    pub nonce_account: Option<Pubkey>,
+    pub nonce_authority_filepath: Option<String>,
+    pub refill_keypair: Option<String>,
+    pub refill_amount: f64,
+    pub max_refills: usize,
+    pub refills: Arc<Mutex<Vec<Instant>>>,
+    pub refilling: Arc<AtomicBool>,
+    pub min_balance: f64,
+    pub balance_check: bool,
+    pub wait_for_funds: Option<u64>,
+    pub balance_recheck_interval: Option<u64>,
+    pub preflight: bool,
+    pub use_min_context_slot: bool,
+    pub retry_on_blockhash_not_found: bool,
+    pub no_confirm: bool,
+    pub dry_run: bool,
+    pub simulate_only: bool,
+    pub dump_unsigned: Option<String>,
+    pub json_logs: bool,
+    pub telemetry: Option<telemetry::TelemetryHook>,
+    pub verbose: bool,
+    pub quiet: bool,
+    pub max_retries: usize,
+    pub max_submit_errors: usize,
+    pub max_unconfirmed_rounds: usize,
+    pub send_timeout: Option<u64>,
+    pub min_submit_interval_ms: u64,
+    pub startup_jitter_ms: u64,
+    pub last_submission: Arc<Mutex<Option<Instant>>>,
+    pub retry_after: Arc<Mutex<Option<Instant>>>,
+    pub retry_delay_ms: u64,
+    pub retry_delay_max_ms: u64,
+    pub retry_delay_multiplier: f64,
+    pub rate_limit_backoff_multiplier: f64,
+    pub blockhash_refresh_attempts: usize,
+    pub confirm_retries: usize,
+    pub confirm_delay_ms: u64,
+    pub confirm_commitment: CommitmentConfig,
+    pub confirm_via: String,
+    pub min_confirmations: u64,
+    pub on_unconfirmed: String,
+    pub jito: bool,
+    pub jito_url: String,
+    pub jito_tip: u64,
+    pub jito_tip_accounts: Vec<Pubkey>,
+    pub lookup_table: Option<Pubkey>,
+    pub tpu_client: Option<Arc<QuicTpuClient>>,
+    pub metrics: Option<Arc<Metrics>>,
+    pub stats_csv: Option<Arc<StatsCsv>>,
+    pub interrupted: Arc<AtomicBool>,
+    pub fees_paid: Arc<AtomicU64>,
+    pub fleet_fees_paid: Option<Arc<AtomicU64>>,
+    pub label: Option<String>,
+    pub progress: Option<Arc<MultiProgress>>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -58,6 +178,15 @@ enum Commands {
     #[command(about = "Fetch the program config")]
     Config(ConfigArgs),
 
+    #[command(about = "Check the RPC, keypairs and balances before mining")]
+    Doctor(DoctorArgs),
+
+    #[command(about = "Estimate your expected rewards at the current difficulty")]
+    Estimate(EstimateArgs),
+
+    #[command(about = "Fetch and decode a miner's proof account")]
+    Inspect(InspectArgs),
+
     #[command(about = "Start mining")]
     Mine(MineArgs),
 
@@ -67,6 +196,9 @@ enum Commands {
     #[command(about = "Stake to earn a rewards multiplier")]
     Stake(StakeArgs),
 
+    #[command(about = "Send ORE to another wallet")]
+    Transfer(TransferArgs),
+
     #[command(about = "Upgrade your ORE tokens from v1 to v2")]
     Upgrade(UpgradeArgs),
 
@@ -86,6 +218,81 @@ struct Args {
     )]
     rpc: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "NETWORK_URL",
+        help = "Network address of an RPC provider to fall back to when submissions fail. May be repeated.",
+        global = true
+    )]
+    rpc_fallback: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "\"KEY: VALUE\"",
+        help = "HTTP header to send with every request to the --rpc endpoint, e.g. an API key. May be repeated. Not sent to fallback RPCs.",
+        value_parser = parse_rpc_header,
+        global = true
+    )]
+    rpc_header: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address of the ORE program to use in place of the mainnet deployment, e.g. one deployed to devnet or a local validator",
+        global = true
+    )]
+    program_id: Option<Pubkey>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "HTTP or SOCKS5 proxy to send RPC, dynamic fee and Jito requests through, e.g. socks5://127.0.0.1:1080. Websocket and TPU traffic connects directly.",
+        global = true
+    )]
+    proxy: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of consecutive submission failures before rotating to the next RPC",
+        default_value = "3",
+        global = true
+    )]
+    rpc_fallback_threshold: usize,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Timeout for each request to the RPC and fallback RPCs",
+        default_value_t = RPC_TIMEOUT,
+        global = true
+    )]
+    rpc_timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Commitment for RPC reads and blockhashes. Lower levels fetch newer blockhashes faster, but risk building on a fork that is dropped and the transaction expiring.",
+        default_value = "confirmed",
+        value_parser = ["processed", "confirmed", "finalized"],
+        global = true
+    )]
+    commitment: String,
+
+    #[arg(
+        long,
+        help = "Broadcast each submission to the primary and all fallback RPCs at once",
+        global = true
+    )]
+    spam_rpcs: bool,
+
+    #[arg(
+        long,
+        help = "Confirm transactions over the RPC websocket instead of polling, when available",
+        global = true
+    )]
+    ws_confirm: bool,
+
     #[clap(
         global = true,
         short = 'C',
@@ -95,22 +302,75 @@ struct Args {
     )]
     config_file: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "TOML file of options to use when they are not given as flags, keyed by flag name, e.g. `priority-fee = 5000`. Options may also be set with ORE_* environment variables, e.g. ORE_PRIORITY_FEE, which take precedence over the file.",
+        global = true
+    )]
+    options: Option<String>,
+
     #[arg(
         long,
         value_name = "KEYPAIR_FILEPATH",
-        help = "Filepath to keypair to use",
+        help = "Keypair to use: a filepath, env:VAR holding a JSON array or base58 key, or - to read JSON from stdin",
         global = true
     )]
     keypair: Option<String>,
 
     #[arg(
-        long,
-        value_name = "FEE_PAYER_FILEPATH",
-        help = "Filepath to keypair to use for fee payer",
+        long = "fee-payer",
+        alias = "fee-payer-filepath",
+        value_name = "KEYPAIR_FILEPATH",
+        help = "Filepath to keypair that pays transaction fees, if different from the signer. Defaults to the signer keypair.",
         global = true
     )]
     fee_payer_filepath: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Durable nonce account to sign transactions with in place of a recent blockhash, so they never expire while retrying. Transactions using it must be sent one at a time.",
+        global = true
+    )]
+    nonce_account: Option<Pubkey>,
+
+    #[arg(
+        long,
+        value_name = "KEYPAIR_FILEPATH",
+        help = "Filepath to the authority keypair of the nonce account. Defaults to the fee payer keypair.",
+        requires = "nonce_account",
+        global = true
+    )]
+    nonce_authority: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEYPAIR",
+        help = "Keypair to top up the fee payer from when its balance drops below --min-balance",
+        global = true
+    )]
+    fee_payer_refill: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Amount of SOL to transfer on each fee payer refill",
+        default_value = "0.05",
+        value_parser = parse_sol,
+        global = true
+    )]
+    refill_amount: f64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum number of fee payer refills in any 24 hour period",
+        default_value = "3",
+        global = true
+    )]
+    max_refills: usize,
+
     #[arg(
         long,
         value_name = "MICROLAMPORTS",
@@ -120,6 +380,30 @@ struct Args {
     )]
     priority_fee: Option<u64>,
 
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Priority fee for mining transactions, in place of --priority-fee and any dynamic fee",
+        global = true
+    )]
+    mine_priority_fee: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Priority fee for claim transactions, including auto-claims, in place of --priority-fee and any dynamic fee",
+        global = true
+    )]
+    claim_priority_fee: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Priority fee for stake transactions, including auto-compounding, in place of --priority-fee and any dynamic fee",
+        global = true
+    )]
+    stake_priority_fee: Option<u64>,
+
     #[arg(
         long,
         value_name = "DYNAMIC_FEE_URL",
@@ -136,6 +420,26 @@ struct Args {
         global = true
     )]
     dynamic_fee_strategy: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PRIORITY_LEVEL",
+        help = "Priority level to request from the helius fee estimator. Defaults to its recommended fee.",
+        value_parser = ["min", "low", "medium", "high", "veryHigh"],
+        global = true
+    )]
+    dynamic_fee_level: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PERCENTILE",
+        help = "Percentile of recent ORE priority fees to pay with the triton fee strategy",
+        default_value = "75",
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        global = true
+    )]
+    dynamic_fee_percentile: u8,
+
     #[arg(
         long,
         value_name = "DYNAMIC_FEE_MAX",
@@ -144,7 +448,448 @@ struct Args {
         global = true
     )]
     dynamic_fee_max: Option<u64>,
-    
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of times to re-query the dynamic fee provider per transaction after rounds that do not land, switching to the fresh fee when it is higher",
+        default_value = "3",
+        global = true
+    )]
+    dynamic_fee_requeries: usize,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Maximum priority fee to pay per transaction, whether static or dynamic",
+        global = true
+    )]
+    priority_fee_max: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Minimum priority fee to pay per transaction, raising static or dynamic fees below it",
+        global = true
+    )]
+    priority_fee_min: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Maximum total priority fee to pay per transaction, given its compute unit limit",
+        value_parser = parse_sol,
+        global = true
+    )]
+    max_priority_fee_per_tx_sol: Option<f64>,
+
+    #[arg(
+        long = "high-fee-threshold",
+        value_name = "SOL",
+        help = "Refuse to send a transaction whose priority fee would cost more than this, to catch mistyped fees. Unlike --max-priority-fee-per-tx-sol, the fee is rejected rather than lowered.",
+        default_value = "0.1",
+        value_parser = parse_sol,
+        global = true
+    )]
+    high_fee_threshold_sol: f64,
+
+    #[arg(
+        long,
+        help = "Send transactions even if their priority fee costs more than --high-fee-threshold",
+        global = true
+    )]
+    allow_high_fee: bool,
+
+    #[arg(
+        long,
+        value_name = "UNITS",
+        help = "Compute unit limit to use for every transaction, instead of simulating or the command's fixed limit",
+        value_parser = clap::value_parser!(u32).range(1..=MAX_COMPUTE_UNIT_LIMIT as i64),
+        global = true
+    )]
+    cu_limit: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Headroom added to simulated compute units for dynamic budgets. Around 10 suits claims and other fixed-cost transactions; raise it to 20-30 if mine transactions run out of compute.",
+        default_value_t = CU_MARGIN_PERCENT,
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        global = true
+    )]
+    cu_margin_percent: u8,
+
+    #[arg(
+        long,
+        help = "Simulate transactions with fixed compute budgets too, like claims and stakes, and send them with the simulated compute units plus --cu-margin-percent, logging the result. Falls back to the fixed limit if simulation fails. Recommended for claim and stake, where fee efficiency matters more than latency.",
+        global = true
+    )]
+    simulate_compute_then_send: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of times to retry a failed simulation before falling back to the maximum compute unit limit",
+        default_value_t = SIMULATION_RETRIES,
+        global = true
+    )]
+    simulation_retries: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of failed submission attempts after which to raise the priority fee. Disabled when 0.",
+        default_value = "0",
+        global = true
+    )]
+    fee_escalation_attempts: usize,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "Factor to raise the priority fee by on each escalation, up to the priority fee cap",
+        default_value = "1.5",
+        value_parser = parse_multiplier,
+        global = true
+    )]
+    fee_escalation_multiplier: f64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Minimum fee payer balance required before submitting a transaction",
+        default_value = "0.005",
+        value_parser = parse_sol,
+        global = true
+    )]
+    min_balance: f64,
+
+    #[arg(
+        long,
+        help = "Skip the fee payer balance check before submitting. Transactions from an empty wallet will then fail on chain.",
+        global = true
+    )]
+    no_balance_check: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Check the fee payer balance again before each retry's submission, at most once per this many seconds, and skip the attempt if it fell to --min-balance. Useful when several miners share a fee payer.",
+        conflicts_with = "no_balance_check",
+        global = true
+    )]
+    balance_recheck_interval: Option<u64>,
+
+    #[arg(
+        long,
+        help = "When the fee payer balance is at or below --min-balance, wait for it to be topped up instead of failing",
+        conflicts_with = "no_balance_check",
+        global = true
+    )]
+    wait_for_funds: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Longest to wait for funds with --wait-for-funds before failing",
+        default_value = "3600",
+        global = true
+    )]
+    wait_for_funds_timeout: u64,
+
+    #[arg(
+        long,
+        help = "Simulate transactions on the RPC before broadcasting them and fail fast on errors",
+        global = true
+    )]
+    preflight: bool,
+
+    #[arg(
+        long,
+        help = "Ask the receiving RPC to reject transactions until it has caught up to the slot their blockhash was fetched at, so lagging nodes fail fast instead of dropping them",
+        global = true
+    )]
+    use_min_context_slot: bool,
+
+    #[arg(
+        long,
+        help = "Re-sign and resend right away, without the retry delay, when the RPC has not seen the blockhash yet. Limited to a few times per transaction.",
+        global = true
+    )]
+    retry_on_blockhash_not_found: bool,
+
+    #[arg(
+        long,
+        help = "Return as soon as the RPC accepts a transaction, without waiting for it to confirm, for confirming out-of-band. The reported signature may not have landed yet.",
+        global = true
+    )]
+    no_confirm: bool,
+
+    #[arg(
+        long,
+        help = "Build and sign transactions and print them instead of sending",
+        global = true
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Format of transaction submission logs. JSON logs emit one object per attempt and disable the spinner.",
+        default_value = "text",
+        value_parser = ["text", "json"],
+        global = true
+    )]
+    log_format: String,
+
+    #[arg(
+        short,
+        long,
+        help = "Log the signer, fee payer, and each instruction of every transaction before sending",
+        global = true
+    )]
+    verbose: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Print plain status lines instead of the spinner. On by default when stdout is not a terminal.",
+        global = true
+    )]
+    quiet: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum number of times to retry submitting a transaction",
+        default_value_t = GATEWAY_RETRIES,
+        global = true
+    )]
+    max_retries: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum number of submissions the RPC may reject before giving up",
+        default_value_t = GATEWAY_RETRIES,
+        global = true
+    )]
+    max_submit_errors: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum number of accepted submissions that may go unconfirmed before giving up",
+        default_value_t = GATEWAY_RETRIES,
+        global = true
+    )]
+    max_unconfirmed_rounds: usize,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Maximum time to spend submitting a single transaction before giving up",
+        global = true
+    )]
+    send_timeout: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Minimum time between transaction submissions, to stay under RPC rate limits",
+        default_value = "0",
+        global = true
+    )]
+    min_submit_interval_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Wait a random time up to this long before the first submission, to desynchronize miners started together",
+        default_value = "0",
+        global = true
+    )]
+    startup_jitter_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Initial delay between transaction submission attempts",
+        default_value_t = GATEWAY_DELAY,
+        global = true
+    )]
+    retry_delay_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Maximum delay between transaction submission attempts",
+        default_value = "5000",
+        global = true
+    )]
+    retry_delay_max_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "Factor the delay between transaction submission attempts grows by after each attempt",
+        default_value = "2.0",
+        value_parser = parse_multiplier,
+        global = true
+    )]
+    retry_delay_multiplier: f64,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "Extra factor applied to the retry delay after the RPC responds with HTTP 429 (Too Many Requests)",
+        default_value = "4.0",
+        value_parser = parse_multiplier,
+        global = true
+    )]
+    rate_limit_backoff_multiplier: f64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of submission attempts after which to re-sign the transaction with a fresh blockhash",
+        default_value = "10",
+        global = true
+    )]
+    blockhash_refresh_attempts: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of times to check for confirmation before resubmitting a transaction",
+        default_value_t = CONFIRM_RETRIES,
+        global = true
+    )]
+    confirm_retries: usize,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Delay between confirmation checks",
+        default_value_t = CONFIRM_DELAY,
+        global = true
+    )]
+    confirm_delay_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Commitment a transaction must reach before it is reported as confirmed, independent of the --commitment used for blockhashes and preflight",
+        default_value = "confirmed",
+        value_parser = ["processed", "confirmed", "finalized"],
+        global = true
+    )]
+    confirm_commitment: String,
+
+    #[arg(
+        long,
+        value_name = "METHOD",
+        help = "RPC method to poll for confirmations. gettransaction works around providers slow to report signature statuses.",
+        default_value = "getsignaturestatuses",
+        value_parser = ["getsignaturestatuses", "gettransaction"],
+        global = true
+    )]
+    confirm_via: String,
+
+    #[arg(
+        long,
+        value_name = "SLOTS",
+        help = "Slots a confirmed transaction must be buried under before it is reported as confirmed, to guard against reorgs",
+        default_value = "0",
+        global = true
+    )]
+    min_confirmations: u64,
+
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help = "After a confirmation miss, keep waiting on the sent transaction until its blockhash needs refreshing, or resubmit it right away",
+        default_value = "wait",
+        value_parser = ["wait", "resubmit"],
+        global = true
+    )]
+    on_unconfirmed: String,
+
+    #[arg(
+        long,
+        help = "Submit transactions as Jito bundles instead of through the RPC",
+        global = true
+    )]
+    jito: bool,
+
+    #[arg(
+        long,
+        value_name = "JITO_URL",
+        help = "Jito block engine endpoint to submit bundles to",
+        default_value = "https://mainnet.block-engine.jito.wtf/api/v1/bundles",
+        global = true
+    )]
+    jito_url: String,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Number of lamports to tip Jito per bundle",
+        default_value = "1000",
+        global = true
+    )]
+    jito_tip: u64,
+
+    #[arg(
+        long = "jito-tip-accounts",
+        alias = "jito-tip-account",
+        value_name = "ADDRESS",
+        help = "Jito tip accounts to pick from at random for each submission. Repeat or comma-separate to give several. Defaults to Jito's published tip accounts.",
+        value_delimiter = ',',
+        default_values = JITO_TIP_ACCOUNTS,
+        global = true
+    )]
+    jito_tip_accounts: Vec<Pubkey>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address lookup table to compile transactions against, sending them as v0 transactions",
+        global = true
+    )]
+    lookup_table: Option<Pubkey>,
+
+    #[arg(
+        long,
+        help = "Send transactions straight to the upcoming leaders over QUIC, falling back to the RPC",
+        global = true
+    )]
+    tpu_direct: bool,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Serve Prometheus metrics about transaction submissions on this port",
+        global = true
+    )]
+    metrics_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address the metrics server listens on. Use 0.0.0.0 to serve other hosts.",
+        default_value = "127.0.0.1",
+        global = true
+    )]
+    metrics_bind: IpAddr,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append a CSV row for every submission to this file, with its fee, attempts, latency and outcome",
+        global = true
+    )]
+    stats_csv: Option<String>,
 
     #[command(subcommand)]
     command: Commands,
@@ -152,7 +897,25 @@ struct Args {
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
+    let argv = args::resolve_options(Args::command(), std::env::args_os().collect())
+        .unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        });
+    let args = Args::parse_from(argv);
+    if let (Some(min), Some(max)) = (args.priority_fee_min, args.priority_fee_max) {
+        if min > max {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--priority-fee-min ({}) must not exceed --priority-fee-max ({})",
+                        min, max
+                    ),
+                )
+                .exit();
+        }
+    }
 
     // Load the config file from custom path, the default path, or use default config values
     let cli_config = if let Some(config_file) = &args.config_file {
@@ -167,20 +930,176 @@ async fn main() {
     };
 
     // Initialize miner.
+    if let Some(program_id) = args.program_id {
+        program::set_program_id(program_id);
+    }
+    if let Some(proxy) = &args.proxy {
+        if let Err(err) = rpc::check_proxy(proxy) {
+            eprintln!("error: Invalid proxy: {}", err);
+            std::process::exit(1);
+        }
+    }
     let cluster = args.rpc.unwrap_or(cli_config.json_rpc_url);
     let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
-    let fee_payer_filepath = args.fee_payer_filepath.unwrap_or(cli_config.keypair_path.clone());
-    let rpc_client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-
-    let miner = Arc::new(Miner::new(
-        Arc::new(rpc_client),
-        args.priority_fee,
-        Some(default_keypair),
-        args.dynamic_fee_url,
-        args.dynamic_fee_strategy,
-        args.dynamic_fee_max,
-        Some(fee_payer_filepath),
+    let fee_payer_filepath = args.fee_payer_filepath.unwrap_or(default_keypair.clone());
+    let ws_url = args
+        .ws_confirm
+        .then(|| solana_cli_config::Config::compute_websocket_url(&cluster));
+    let tpu_ws_url = solana_cli_config::Config::compute_websocket_url(&cluster);
+    let rpc_timeout = Duration::from_secs(args.rpc_timeout);
+    let commitment = CommitmentConfig::from_str(&args.commitment).unwrap();
+    let rpc_client = Arc::new(rpc::new_rpc_client(
+        cluster,
+        rpc_timeout,
+        commitment,
+        &args.rpc_header,
+        args.proxy.as_deref(),
     ));
+    let tpu_client = if args.tpu_direct {
+        tpu::connect_tpu(rpc_client.clone(), &tpu_ws_url).await
+    } else {
+        None
+    };
+    let rpc_fallbacks = args
+        .rpc_fallback
+        .into_iter()
+        .map(|url| {
+            Arc::new(rpc::new_rpc_client(
+                url,
+                rpc_timeout,
+                commitment,
+                &[],
+                args.proxy.as_deref(),
+            ))
+        })
+        .collect();
+
+    let stats_csv = args.stats_csv.as_ref().map(|path| {
+        Arc::new(StatsCsv::open(path).unwrap_or_else(|err| {
+            eprintln!("error: Could not open stats file `{}`: {}", path, err);
+            std::process::exit(1);
+        }))
+    });
+
+    let simulate_only = match &args.command {
+        Commands::Claim(args) => args.simulate_only,
+        Commands::Stake(args) => args.simulate_only,
+        Commands::Transfer(args) => args.simulate_only,
+        _ => false,
+    };
+    let dump_unsigned = match &args.command {
+        Commands::Claim(args) => args.dump_unsigned.clone(),
+        Commands::Stake(args) => args.dump_unsigned.clone(),
+        Commands::Transfer(args) => args.dump_unsigned.clone(),
+        _ => None,
+    };
+
+    let miner = Arc::new(Miner {
+        rpc_client,
+        blockhash_cache: Arc::new(BlockhashCache::default()),
+        rpc_fallbacks,
+        rpc_fallback_threshold: args.rpc_fallback_threshold,
+        active_rpc: Arc::new(AtomicUsize::new(0)),
+        spam_rpcs: args.spam_rpcs,
+        ws_url,
+        priority_fee: args.priority_fee,
+        mine_priority_fee: args.mine_priority_fee,
+        claim_priority_fee: args.claim_priority_fee,
+        stake_priority_fee: args.stake_priority_fee,
+        keypair_filepath: Some(default_keypair),
+        rpc_header_names: args
+            .rpc_header
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect(),
+        http_client: rpc::new_http_client(args.proxy.as_deref()),
+        dynamic_fee_url: args.dynamic_fee_url,
+        dynamic_fee_strategy: args.dynamic_fee_strategy,
+        dynamic_fee_level: args.dynamic_fee_level,
+        dynamic_fee_percentile: args.dynamic_fee_percentile,
+        dynamic_fee_max: args.dynamic_fee_max,
+        dynamic_fee_requeries: args.dynamic_fee_requeries,
+        priority_fee_max: args.priority_fee_max,
+        priority_fee_min: args.priority_fee_min,
+        max_priority_fee_per_tx_sol: args.max_priority_fee_per_tx_sol,
+        high_fee_threshold_sol: args.high_fee_threshold_sol,
+        allow_high_fee: args.allow_high_fee,
+        cu_limit: args.cu_limit,
+        cu_margin_percent: args.cu_margin_percent,
+        simulate_compute_then_send: args.simulate_compute_then_send,
+        simulation_retries: args.simulation_retries,
+        fee_escalation_attempts: args.fee_escalation_attempts,
+        fee_escalation_multiplier: args.fee_escalation_multiplier,
+        fee_payer_filepath: Some(fee_payer_filepath),
+        nonce_account: args.nonce_account,
+        nonce_authority_filepath: args.nonce_authority,
+        refill_keypair: args.fee_payer_refill,
+        refill_amount: args.refill_amount,
+        max_refills: args.max_refills,
+        refills: Arc::new(Mutex::new(vec![])),
+        refilling: Arc::new(AtomicBool::new(false)),
+        min_balance: args.min_balance,
+        balance_check: !args.no_balance_check,
+        balance_recheck_interval: args.balance_recheck_interval,
+        wait_for_funds: args.wait_for_funds.then_some(args.wait_for_funds_timeout),
+        preflight: args.preflight,
+        use_min_context_slot: args.use_min_context_slot,
+        retry_on_blockhash_not_found: args.retry_on_blockhash_not_found,
+        no_confirm: args.no_confirm,
+        dry_run: args.dry_run,
+        simulate_only,
+        dump_unsigned,
+        json_logs: args.log_format == "json",
+        telemetry: (args.log_format == "json").then(telemetry::json_logger),
+        verbose: args.verbose,
+        quiet: args.quiet || !std::io::stdout().is_terminal(),
+        max_retries: args.max_retries,
+        max_submit_errors: args.max_submit_errors,
+        max_unconfirmed_rounds: args.max_unconfirmed_rounds,
+        send_timeout: args.send_timeout,
+        min_submit_interval_ms: args.min_submit_interval_ms,
+        startup_jitter_ms: args.startup_jitter_ms,
+        last_submission: Arc::new(Mutex::new(None)),
+        retry_after: Arc::new(Mutex::new(None)),
+        retry_delay_ms: args.retry_delay_ms,
+        retry_delay_max_ms: args.retry_delay_max_ms,
+        retry_delay_multiplier: args.retry_delay_multiplier,
+        rate_limit_backoff_multiplier: args.rate_limit_backoff_multiplier,
+        blockhash_refresh_attempts: args.blockhash_refresh_attempts,
+        confirm_retries: args.confirm_retries,
+        confirm_delay_ms: args.confirm_delay_ms,
+        confirm_commitment: CommitmentConfig::from_str(&args.confirm_commitment).unwrap(),
+        min_confirmations: args.min_confirmations,
+        confirm_via: args.confirm_via,
+        on_unconfirmed: args.on_unconfirmed,
+        jito: args.jito,
+        jito_url: args.jito_url,
+        jito_tip: args.jito_tip,
+        jito_tip_accounts: args.jito_tip_accounts,
+        lookup_table: args.lookup_table,
+        tpu_client,
+        metrics: args.metrics_port.map(|_| Arc::new(Metrics::default())),
+        stats_csv,
+        interrupted: Arc::new(AtomicBool::new(false)),
+        fees_paid: Arc::new(AtomicU64::new(0)),
+        fleet_fees_paid: None,
+        label: None,
+        progress: None,
+    });
+    if let Some(port) = args.metrics_port {
+        miner.start_metrics_server(SocketAddr::new(args.metrics_bind, port));
+    }
+
+    // Let in-flight work wind down on the first Ctrl-C, and exit on the second
+    let interrupted = miner.interrupted.clone();
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if interrupted.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+            eprintln!("\nInterrupted. Finishing up... (press Ctrl-C again to exit now)");
+        }
+    });
 
     // Execute user command.
     match args.command {
@@ -190,27 +1109,39 @@ async fn main() {
         Commands::Benchmark(args) => {
             miner.benchmark(args).await;
         }
-        Commands::Busses(_) => {
-            miner.busses().await;
+        Commands::Busses(args) => {
+            miner.busses(args).await;
         }
         Commands::Claim(args) => {
             miner.claim(args).await;
         }
-        Commands::Close(_) => {
-            miner.close().await;
+        Commands::Close(args) => {
+            miner.close(args).await;
         }
         Commands::Config(_) => {
             miner.config().await;
         }
+        Commands::Doctor(_) => {
+            miner.doctor().await;
+        }
+        Commands::Estimate(args) => {
+            miner.estimate(args).await;
+        }
+        Commands::Inspect(args) => {
+            miner.inspect(args).await;
+        }
         Commands::Mine(args) => {
             miner.mine(args).await;
         }
-        Commands::Rewards(_) => {
-            miner.rewards().await;
+        Commands::Rewards(args) => {
+            miner.rewards(args).await;
         }
         Commands::Stake(args) => {
             miner.stake(args).await;
         }
+        Commands::Transfer(args) => {
+            miner.transfer(args).await;
+        }
         Commands::Upgrade(args) => {
             miner.upgrade(args).await;
         }
@@ -222,39 +1153,64 @@ async fn main() {
 }
 
 impl Miner {
-    pub fn new(
-        rpc_client: Arc<RpcClient>,
-        priority_fee: Option<u64>,
-        keypair_filepath: Option<String>,
-        dynamic_fee_url: Option<String>,
-        dynamic_fee_strategy: Option<String>,
-        dynamic_fee_max: Option<u64>,
-        fee_payer_filepath: Option<String>,
-    ) -> Self {
-        Self {
-            rpc_client,
-            keypair_filepath,
-            priority_fee,
-            dynamic_fee_url,
-            dynamic_fee_strategy,
-            dynamic_fee_max,
-            fee_payer_filepath
+    pub fn signer(&self) -> Result<Keypair, String> {
+        match &self.keypair_filepath {
+            Some(filepath) => load_keypair(filepath),
+            None => Err("No keypair provided".to_string()),
+        }
+    }
+
+    pub fn new_progress_bar(&self) -> ProgressBar {
+        let mut progress_bar = spinner::new_progress_bar();
+        if self.json_logs {
+            progress_bar.set_draw_target(ProgressDrawTarget::hidden());
+            return progress_bar;
+        }
+        if self.quiet {
+            // Plain status lines instead of a spinner, for logs
+            progress_bar.disable_steady_tick();
+            progress_bar.set_draw_target(plain_draw_target());
+            progress_bar.set_style(ProgressStyle::default_spinner().template("{msg}").unwrap());
         }
+        if let Some(progress) = &self.progress {
+            // Give each wallet its own line when mining several at once
+            progress_bar = progress.add(progress_bar);
+            let template = if self.quiet {
+                "{prefix} {msg}"
+            } else {
+                "{spinner:.green} {prefix} {wide_msg}"
+            };
+            progress_bar.set_style(ProgressStyle::default_spinner().template(template).unwrap());
+            progress_bar.set_prefix(self.label.clone().unwrap_or_default());
+        }
+        progress_bar
     }
 
-    pub fn signer(&self) -> Keypair {
-        match self.keypair_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath.clone())
-                .expect(format!("No keypair found at {}", filepath).as_str()),
-            None => panic!("No keypair provided"),
+    // Prints above any progress bars, tagged with the wallet when mining several at once
+    pub fn println(&self, message: String) {
+        match (&self.progress, &self.label) {
+            (Some(progress), Some(label)) => {
+                for line in message.lines() {
+                    let _ = progress.println(format!("{} {}", label, line));
+                }
+            }
+            _ => println!("{}", message),
         }
     }
 
-    pub fn fee_payer(&self) -> Keypair {
-        match self.fee_payer_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath.clone())
-                .expect(format!("No fee payer keypair found at {}", filepath).as_str()),
-            None => panic!("No fee payer keypair provided"),
+    pub fn fee_payer(&self) -> Result<Keypair, String> {
+        match &self.fee_payer_filepath {
+            Some(filepath) => load_keypair(filepath).map_err(|err| format!("Fee payer: {}", err)),
+            None => Err("No fee payer keypair provided".to_string()),
         }
     }
+
+    pub fn nonce_authority(&self) -> Result<Option<Keypair>, String> {
+        self.nonce_authority_filepath
+            .as_ref()
+            .map(|filepath| {
+                load_keypair(filepath).map_err(|err| format!("Nonce authority: {}", err))
+            })
+            .transpose()
+    }
 }
@@ -0,0 +1,175 @@
+use std::{
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use colored::*;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::Miner;
+
+const LATENCY_BUCKETS: &[f64] = &[0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+const PRIORITY_FEE_BUCKETS: &[f64] =
+    &[0.0, 1_000.0, 10_000.0, 100_000.0, 1_000_000.0, 10_000_000.0];
+
+pub struct Metrics {
+    pub submissions: AtomicU64,
+    pub confirmations: AtomicU64,
+    pub failures: AtomicU64,
+    pub retries: AtomicU64,
+    pub confirmation_latency: Histogram,
+    pub priority_fee: Histogram,
+}
+
+pub struct Histogram {
+    bounds: &'static [f64],
+    state: Mutex<HistogramState>,
+}
+
+struct HistogramState {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram {
+            bounds,
+            state: Mutex::new(HistogramState {
+                counts: vec![0; bounds.len()],
+                sum: 0.0,
+                count: 0,
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let mut state = self.state.lock().unwrap();
+        for (i, bound) in self.bounds.iter().enumerate() {
+            if value <= *bound {
+                state.counts[i] += 1;
+            }
+        }
+        state.sum += value;
+        state.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let state = self.state.lock().unwrap();
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, count) in self.bounds.iter().zip(state.counts.iter()) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, state.count);
+        let _ = writeln!(out, "{}_sum {}", name, state.sum);
+        let _ = writeln!(out, "{}_count {}", name, state.count);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            submissions: AtomicU64::new(0),
+            confirmations: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            confirmation_latency: Histogram::new(LATENCY_BUCKETS),
+            priority_fee: Histogram::new(PRIORITY_FEE_BUCKETS),
+        }
+    }
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, help, counter) in [
+            (
+                "ore_submissions_total",
+                "Transactions sent to the cluster",
+                &self.submissions,
+            ),
+            (
+                "ore_confirmations_total",
+                "Transactions confirmed",
+                &self.confirmations,
+            ),
+            (
+                "ore_failures_total",
+                "Submissions rejected or failed on chain",
+                &self.failures,
+            ),
+            ("ore_retries_total", "Submission retries", &self.retries),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        self.confirmation_latency.render(
+            &mut out,
+            "ore_confirmation_latency_seconds",
+            "Time from first submission to confirmation",
+        );
+        self.priority_fee.render(
+            &mut out,
+            "ore_priority_fee_microlamports",
+            "Priority fee of confirmed transactions",
+        );
+        out
+    }
+}
+
+impl Miner {
+    // Updates the metrics, if enabled
+    pub fn record(&self, f: impl FnOnce(&Metrics)) {
+        if let Some(metrics) = &self.metrics {
+            f(metrics);
+        }
+    }
+
+    pub fn start_metrics_server(&self, address: SocketAddr) {
+        let Some(metrics) = self.metrics.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            let listener = match TcpListener::bind(address).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    println!(
+                        "{}: Failed to start metrics server on {}: {}",
+                        "ERROR".bold().red(),
+                        address,
+                        err
+                    );
+                    return;
+                }
+            };
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    continue;
+                };
+                let metrics: Arc<Metrics> = metrics.clone();
+                tokio::spawn(async move {
+                    // Every request gets the metrics page, regardless of path
+                    let mut request = [0u8; 1024];
+                    let _ = stream.read(&mut request).await;
+                    let body = metrics.render();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+    }
+}
@@ -1,112 +1,395 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use colored::*;
+use core_affinity::CoreId;
 use drillx::{
     equix::{self},
     Hash, Solution,
 };
 use ore_api::{
-    consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION},
+    consts::{BUS_COUNT, EPOCH_DURATION},
     state::{Config, Proof},
 };
 use rand::Rng;
-use solana_program::pubkey::Pubkey;
-use solana_rpc_client::spinner;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_sdk::signer::Signer;
 
 use crate::{
     args::MineArgs,
-    send_request::ComputeBudget,
-    utils::{amount_u64_to_string, get_clock, get_config, get_proof_with_authority, proof_pubkey},
+    cu_limits::CU_LIMIT_RESET,
+    program::bus_addresses,
+    send_request::{ComputeBudget, SubmitError},
+    state::MiningState,
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, exit_on_error, get_clock, get_config,
+        get_proof_with_authority, proof_pubkey,
+    },
     Miner,
 };
 
+const STATE_SAVE_INTERVAL: u64 = 5;
+// Milliseconds between hashrate updates, kept coarse to leave the CPU to hashing. Quiet mode
+// writes a line per update, so it updates less often.
+const PROGRESS_INTERVAL_MS: u64 = 250;
+const QUIET_PROGRESS_INTERVAL_MS: u64 = 1_000;
+// Difficulties per row of the --difficulty-histogram chart, and the width of its longest bar
+const HISTOGRAM_BUCKET_WIDTH: u32 = 2;
+const HISTOGRAM_BAR_WIDTH: u64 = 40;
+
+/// The slice of the nonce space this machine searches. Each worker in a cluster takes an equal
+/// share, divided again between its threads.
+#[derive(Clone, Copy)]
+struct NonceSpace {
+    worker_index: u64,
+    worker_count: u64,
+    threads: u64,
+}
+
+/// When threads stop hashing: at the cutoff, once a hash reaches the min difficulty, or as soon
+/// as a hash reaches the target difficulty after the earliest submission time.
+#[derive(Clone, Copy)]
+struct SearchLimits {
+    cutoff_time: u64,
+    min_difficulty: u32,
+    target_difficulty: Option<u32>,
+    earliest_time: u64,
+}
+
+impl NonceSpace {
+    fn first_nonce(&self, thread: u64) -> u64 {
+        let worker_span = u64::MAX.saturating_div(self.worker_count);
+        worker_span
+            .saturating_mul(self.worker_index)
+            .saturating_add(
+                worker_span
+                    .saturating_div(self.threads)
+                    .saturating_mul(thread),
+            )
+    }
+}
+
 impl Miner {
     pub async fn mine(&self, args: MineArgs) {
-        // Register, if needed.
-        let signer = self.signer();
-        self.open().await;
-
         // Check num threads
         self.check_num_cores(args.threads);
+        if args.worker_index >= args.worker_count {
+            println!(
+                "{}: --worker-index must be less than --worker-count ({})",
+                "ERROR".bold().red(),
+                args.worker_count
+            );
+            return;
+        }
+        let core_ids = if args.cpu_affinity {
+            pin_cores(args.threads)
+        } else {
+            vec![]
+        };
+
+        // Keep a fresh blockhash on hand for submissions
+        self.start_blockhash_refresher();
+
+        if let Some(keypair_dir) = &args.keypair_dir {
+            self.mine_wallets(&args, keypair_dir, &core_ids).await;
+            return;
+        }
+        self.mine_loop(&args, args.threads, &core_ids, args.state_file.as_deref())
+            .await;
+    }
+
+    // Mines with the signer until interrupted
+    pub async fn mine_loop(
+        &self,
+        args: &MineArgs,
+        threads: u64,
+        core_ids: &[CoreId],
+        state_file: Option<&str>,
+    ) {
+        // Register, if needed.
+        let signer = exit_on_error(self.signer());
+        self.open().await;
 
         // Start mining loop
-        loop {
+        let nonce_space = NonceSpace {
+            worker_index: args.worker_index,
+            worker_count: args.worker_count,
+            threads,
+        };
+        let mut difficulties: BTreeMap<u32, u64> = BTreeMap::new();
+        let mut hashrate: Option<u64> = None;
+        let mut proof_attempts = 0;
+        while !self.interrupted.load(Ordering::Relaxed) {
             // Fetch proof
             let config = get_config(&self.rpc_client).await;
             let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
-            println!(
+            self.println(format!(
                 "\nStake: {} ORE\n  Multiplier: {:12}x",
                 amount_u64_to_string(proof.balance),
                 calculate_multiplier(proof.balance, config.top_balance)
-            );
+            ));
 
             // Calc cutoff time
             let cutoff_time = self.get_cutoff(proof, args.buffer_time).await;
 
-            // Run drillx
-            let (solution,best_diff) = Self::find_hash_par(
-                proof,
+            // Run drillx. A single submission takes the first hash worth submitting.
+            let mut limits = SearchLimits {
                 cutoff_time,
-                args.threads,
-                config.min_difficulty as u32,
-            )
-            .await;
+                min_difficulty: config.min_difficulty as u32,
+                target_difficulty: None,
+                earliest_time: cutoff_time,
+            };
+            if args.once {
+                limits.cutoff_time = 0;
+                limits.min_difficulty = args.min_difficulty.unwrap_or(0).max(limits.min_difficulty);
+            }
+
+            // Mine toward a difficulty the last measured hashrate should reach in the interval
+            if let (Some(interval), Some(hashrate)) = (args.target_submit_interval, hashrate) {
+                let target_difficulty = (hashrate.saturating_mul(interval) as f64)
+                    .max(1.0)
+                    .log2()
+                    .floor() as u32;
+                limits.cutoff_time = cutoff_time.min(interval);
+                limits.target_difficulty =
+                    Some(target_difficulty.max(config.min_difficulty as u32));
+                self.println(format!(
+                    "  Target difficulty: {} (submitting within {} sec at {} H/sec)",
+                    limits.target_difficulty.unwrap(),
+                    limits.cutoff_time,
+                    hashrate
+                ));
+            }
+            let started_at = Instant::now();
+            let (solution, best_diff, hashes) = self
+                .find_hash_par(proof, limits, nonce_space, core_ids, state_file)
+                .await;
+            hashrate = Some(hashes.saturating_div(started_at.elapsed().as_secs().max(1)));
 
+            // Hold the solution until the cutoff, since the program rejects early submissions
+            if args.once {
+                let remaining = cutoff_time.saturating_sub(started_at.elapsed().as_secs());
+                if remaining > 0 {
+                    self.println(format!(
+                        "Found difficulty {}. Submitting in {} sec...",
+                        best_diff, remaining
+                    ));
+                    tokio::time::sleep(Duration::from_secs(remaining)).await;
+                }
+            }
+
+            // Don't submit a solution cut short by an interrupt
+            if self.interrupted.load(Ordering::Relaxed) {
+                break;
+            }
+
+            // Tally the best difficulty of each attempt
+            *difficulties
+                .entry(best_diff / HISTOGRAM_BUCKET_WIDTH * HISTOGRAM_BUCKET_WIDTH)
+                .or_default() += 1;
+            proof_attempts += 1;
+            if let Some(interval) = args.difficulty_histogram {
+                if proof_attempts % interval == 0 {
+                    self.print_histogram(&difficulties);
+                }
+            }
+
+            // Skip solutions below the requested difficulty
+            if let Some(min_difficulty) = args.min_difficulty {
+                if best_diff < min_difficulty {
+                    let skipped = SubmitError::SubmissionSkipped {
+                        difficulty: best_diff,
+                        min_difficulty,
+                    };
+                    self.println(format!("{}: {}", "WARNING".bold().yellow(), skipped));
+                    continue;
+                }
+            }
+
+            // Advance an expired epoch first, since the program rejects solutions until it resets.
+            // Fall back to resetting in the mine transaction if that fails.
+            let needs_reset = if args.auto_reset {
+                !self.auto_reset().await
+            } else {
+                self.should_reset(config).await && rand::thread_rng().gen_range(0..100).eq(&0)
+            };
 
             // Submit most difficult hash
             let mut compute_budget = 500_000;
             let mut ixs = vec![ore_api::instruction::auth(proof_pubkey(signer.pubkey()))];
-            if self.should_reset(config).await && rand::thread_rng().gen_range(0..100).eq(&0) {
+            if needs_reset {
                 compute_budget += 100_000;
                 ixs.push(ore_api::instruction::reset(signer.pubkey()));
             }
 
+            ixs.push(ore_api::instruction::mine(
+                signer.pubkey(),
+                signer.pubkey(),
+                find_bus(),
+                solution,
+            ));
+            let outcome = match self
+                .send_request(&ixs, ComputeBudget::Fixed(compute_budget), false)
+                .await
+            {
+                Ok(outcome) => outcome,
+                Err(SubmitError::Interrupted) => break,
+                Err(_) if args.once => std::process::exit(1),
+                Err(_) => continue,
+            };
+            if let Some(slot) = outcome.landed_slot {
+                self.println(format!(
+                    "  Landed in slot {} after {} attempt(s) at CU limit {}, price {} microlamports/CU\n  Fee: {} SOL (session total: {} SOL{})",
+                    slot,
+                    outcome.attempts,
+                    outcome.cu_limit,
+                    outcome.priority_fee,
+                    lamports_to_sol(outcome.fee),
+                    lamports_to_sol(self.fees_paid.load(Ordering::Relaxed)),
+                    self.fleet_fees_message()
+                ));
+            }
+            if args.once {
+                println!("{}", outcome.signature);
+                std::process::exit(0);
+            }
+
+            // Sweep rewards once they cross the threshold
+            if let Some(auto_claim_at) = args.auto_claim_at {
+                if let Some((tokens, amount)) =
+                    self.auto_claim(amount_f64_to_u64(auto_claim_at)).await
+                {
+                    // Put claimed rewards straight back to work
+                    if args.auto_compound {
+                        self.stake_tokens(tokens, amount).await;
+                    }
+                }
+            }
+        }
+
+        if args.difficulty_histogram.is_some() && !difficulties.is_empty() {
+            self.print_histogram(&difficulties);
+        }
+        self.println(format!(
+            "\nSession fees: {} SOL{}",
+            lamports_to_sol(self.fees_paid.load(Ordering::Relaxed)),
+            self.fleet_fees_message()
+        ));
+
+        // A single submission only gets here without landing
+        if args.once {
+            std::process::exit(1);
+        }
+    }
 
-	    ixs.push(ore_api::instruction::mine(
-		signer.pubkey(),
-		signer.pubkey(),
-		find_bus(),
-		solution,
-	    ));
-
-		// self.send_request(&ixs, ComputeBudget::Fixed(compute_budget), false,best_diff);
-
-		
-	   if best_diff.ge(&18) {
-		self.send_request(&ixs, ComputeBudget::Fixed(compute_budget), true,best_diff)
-		.await.ok();
-	    } else {
-		self.send_request(&ixs, ComputeBudget::Fixed(compute_budget), false,best_diff)
-		.await.ok();	
-	     }
-	
+    // Prints the count of proof attempts per difficulty bucket, scaled to the largest bucket
+    fn print_histogram(&self, difficulties: &BTreeMap<u32, u64>) {
+        let total: u64 = difficulties.values().sum();
+        let largest = difficulties.values().copied().max().unwrap_or(1);
+        let mut s = format!("\n{} ({} attempts)", "Best difficulties".bold(), total);
+        for (bucket, count) in difficulties {
+            s = format!(
+                "{}\n  {:>3}-{:<3} {:>6} {}",
+                s,
+                bucket,
+                bucket + HISTOGRAM_BUCKET_WIDTH - 1,
+                count,
+                "#".repeat((count * HISTOGRAM_BAR_WIDTH).div_ceil(largest) as usize)
+            );
         }
+        self.println(s);
     }
 
+    // The fees paid by every wallet, to show next to this wallet's when mining several
+    fn fleet_fees_message(&self) -> String {
+        match &self.fleet_fees_paid {
+            Some(fleet_fees_paid) => format!(
+                ", fleet total: {} SOL",
+                lamports_to_sol(fleet_fees_paid.load(Ordering::Relaxed))
+            ),
+            None => String::new(),
+        }
+    }
+
+    // Returns the best solution found, its difficulty, and the number of hashes computed
     async fn find_hash_par(
+        &self,
         proof: Proof,
-        cutoff_time: u64,
-        threads: u64,
-        min_difficulty: u32,
-    ) -> (Solution,u32) {
-	loop {
-		
-	// Dispatch job to each thread
-        let progress_bar = Arc::new(spinner::new_progress_bar());
+        limits: SearchLimits,
+        nonce_space: NonceSpace,
+        core_ids: &[CoreId],
+        state_file: Option<&str>,
+    ) -> (Solution, u32, u64) {
+        let SearchLimits {
+            cutoff_time,
+            min_difficulty,
+            target_difficulty,
+            earliest_time,
+        } = limits;
+        let progress_bar = Arc::new(self.new_progress_bar());
+
+        // Resume from the best hash saved for this challenge, if any
+        let resumed = state_file
+            .and_then(MiningState::load)
+            .filter(|state| state.challenge == proof.challenge)
+            .and_then(|state| {
+                drillx::hash(&proof.challenge, &state.nonce.to_le_bytes())
+                    .ok()
+                    .map(|hx| (state.nonce, hx.d, hx.h, hx.difficulty()))
+            });
+        if let Some((_, _, _, difficulty)) = resumed {
+            progress_bar.println(format!("Resuming from saved difficulty {}", difficulty));
+        }
+        let saved_best = Arc::new(Mutex::new(
+            resumed.map(|(nonce, _, _, difficulty)| (nonce, difficulty)),
+        ));
+
+        // Shared across threads for the live hashrate display
+        let progress_interval = Duration::from_millis(match self.quiet {
+            true => QUIET_PROGRESS_INTERVAL_MS,
+            false => PROGRESS_INTERVAL_MS,
+        });
+        let hash_count = Arc::new(AtomicU64::new(0));
+        let best_so_far = Arc::new(AtomicU32::new(
+            resumed.map_or(0, |(_, _, _, difficulty)| difficulty),
+        ));
+
+        // Dispatch job to each thread
         progress_bar.set_message("Mining...");
-        let handles: Vec<_> = (0..threads)
+        let handles: Vec<_> = (0..nonce_space.threads)
             .map(|i| {
                 std::thread::spawn({
-                    let proof = proof.clone();
                     let progress_bar = progress_bar.clone();
+                    let interrupted = self.interrupted.clone();
+                    let core_id = core_ids.get(i as usize).copied();
+                    let state_file = state_file.map(String::from);
+                    let saved_best = saved_best.clone();
+                    let hash_count = hash_count.clone();
+                    let best_so_far = best_so_far.clone();
                     let mut memory = equix::SolverMemory::new();
                     move || {
+                        if let Some(core_id) = core_id {
+                            core_affinity::set_for_current(core_id);
+                        }
                         let timer = Instant::now();
-                        let mut nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
+                        let first_nonce = nonce_space.first_nonce(i);
+                        let mut nonce = first_nonce;
                         let mut best_nonce = nonce;
                         let mut best_difficulty = 0;
                         let mut best_hash = Hash::default();
+                        if let Some((nonce, d, h, difficulty)) = resumed {
+                            best_nonce = nonce;
+                            best_difficulty = difficulty;
+                            best_hash = Hash { d, h };
+                        }
+                        let mut last_saved = (Instant::now(), *saved_best.lock().unwrap());
+                        let mut last_update = Instant::now();
                         loop {
                             // Create hash
                             if let Ok(hx) = drillx::hash_with_memory(
@@ -115,37 +398,90 @@ impl Miner {
                                 &nonce.to_le_bytes(),
                             ) {
                                 let difficulty = hx.difficulty();
-                                if difficulty.gt(&best_difficulty) & difficulty.ge(&18) {
+                                if difficulty.gt(&best_difficulty) {
                                     best_nonce = nonce;
                                     best_difficulty = difficulty;
                                     best_hash = hx;
+                                    best_so_far.fetch_max(difficulty, Ordering::Relaxed);
+                                    if state_file.is_some() {
+                                        let mut saved_best = saved_best.lock().unwrap();
+                                        if saved_best.map_or(true, |(_, d)| difficulty > d) {
+                                            *saved_best = Some((nonce, difficulty));
+                                        }
+                                    }
                                 }
                             }
 
                             // Exit if time has elapsed
                             if nonce % 100 == 0 {
-                                if timer.elapsed().as_secs().ge(&cutoff_time) {
-                                    if best_difficulty.ge(&min_difficulty) {
-                                        // Mine until min difficulty has been met
-                                        break;
+                                hash_count.fetch_add(100, Ordering::Relaxed);
+                                if interrupted.load(Ordering::Relaxed) {
+                                    break;
+                                }
+                                if target_difficulty
+                                    .map_or(false, |target| best_difficulty >= target)
+                                    && timer.elapsed().as_secs() >= earliest_time
+                                {
+                                    // Stop early once the target has been met
+                                    break;
+                                }
+                                if timer.elapsed().as_secs().ge(&cutoff_time)
+                                    && best_difficulty.ge(&min_difficulty)
+                                {
+                                    // Mine until min difficulty has been met
+                                    break;
+                                }
+                                if i == 0 && last_update.elapsed() >= progress_interval {
+                                    last_update = Instant::now();
+                                    let elapsed = timer.elapsed();
+                                    let hashrate = hash_count.load(Ordering::Relaxed) as f64
+                                        / elapsed.as_secs_f64().max(0.001);
+                                    let best = best_so_far.load(Ordering::Relaxed);
+                                    let remaining =
+                                        cutoff_time.saturating_sub(elapsed.as_secs());
+                                    progress_bar.set_message(match target_difficulty {
+                                        Some(target) => format!(
+                                            "Mining... {:.0} H/sec, {} sec elapsed, {} sec remaining, best {} of target {}",
+                                            hashrate, elapsed.as_secs(), remaining, best, target
+                                        ),
+                                        None => format!(
+                                            "Mining... {:.0} H/sec, {} sec elapsed, {} sec remaining, best {}",
+                                            hashrate, elapsed.as_secs(), remaining, best
+                                        ),
+                                    });
+                                }
+
+                                // Periodically persist the best hash across all threads
+                                if let (0, Some(path)) = (i, &state_file) {
+                                    let best = *saved_best.lock().unwrap();
+                                    if last_saved.0.elapsed().as_secs() >= STATE_SAVE_INTERVAL
+                                        && best != last_saved.1
+                                    {
+                                        if let Some((nonce, difficulty)) = best {
+                                            let state = MiningState {
+                                                challenge: proof.challenge,
+                                                nonce,
+                                                difficulty,
+                                            };
+                                            if let Err(err) = state.save(path) {
+                                                progress_bar.println(format!(
+                                                    "{} Failed to save mining state: {}",
+                                                    "WARNING".bold().yellow(),
+                                                    err
+                                                ));
+                                            }
+                                        }
+                                        last_saved = (Instant::now(), best);
                                     }
-                                } else if i == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... ({} sec remaining)",
-                                        cutoff_time.saturating_sub(timer.elapsed().as_secs()),
-                                    ));
                                 }
                             }
 
                             // Increment nonce
                             nonce += 1;
-				if best_difficulty.ge(&18) {
-				println!("best_difficulty: {} ",best_difficulty);					
-				}
                         }
 
                         // Return the best nonce
-                        (best_nonce, best_difficulty, best_hash)
+                        (best_nonce, best_difficulty, best_hash, nonce - first_nonce)
                     }
                 })
             })
@@ -155,13 +491,22 @@ impl Miner {
         let mut best_nonce = 0;
         let mut best_difficulty = 0;
         let mut best_hash = Hash::default();
-        for h in handles {
-            if let Ok((nonce, difficulty, hash)) = h.join() {
-                if difficulty > best_difficulty {
-                    best_difficulty = difficulty;
-                    best_nonce = nonce;
-                    best_hash = hash;
-                }
+        // Wait off the async runtime so other wallets' loops keep running
+        let results = tokio::task::spawn_blocking(move || {
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().ok())
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+        let mut hashes = 0;
+        for (nonce, difficulty, hash, count) in results {
+            hashes += count;
+            if difficulty > best_difficulty {
+                best_difficulty = difficulty;
+                best_nonce = nonce;
+                best_hash = hash;
             }
         }
 
@@ -172,11 +517,11 @@ impl Miner {
             best_difficulty
         ));
 
-	if best_difficulty.ge(&18) {
-	// 传入最大困难值
-         return  (Solution::new(best_hash.d, best_nonce.to_le_bytes()),best_difficulty);
-	}
-	}
+        (
+            Solution::new(best_hash.d, best_nonce.to_le_bytes()),
+            best_difficulty,
+            hashes,
+        )
     }
 
     pub fn check_num_cores(&self, threads: u64) {
@@ -201,6 +546,40 @@ impl Miner {
             .le(&clock.unix_timestamp)
     }
 
+    // Resets the epoch if it has expired. Returns false if it is still expired afterwards.
+    async fn auto_reset(&self) -> bool {
+        let config = get_config(&self.rpc_client).await;
+        if !self.epoch_expired(config).await {
+            return true;
+        }
+        self.println("Epoch expired. Resetting...".to_string());
+        let ix = ore_api::instruction::reset(exit_on_error(self.signer()).pubkey());
+        if let Err(err) = self
+            .send_request(&[ix], ComputeBudget::Fixed(CU_LIMIT_RESET), false)
+            .await
+        {
+            // Another miner may have reset it first
+            let config = get_config(&self.rpc_client).await;
+            if self.epoch_expired(config).await {
+                self.println(format!(
+                    "{}: Failed to reset epoch: {}",
+                    "WARNING".bold().yellow(),
+                    err
+                ));
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn epoch_expired(&self, config: Config) -> bool {
+        let clock = get_clock(&self.rpc_client).await;
+        config
+            .last_reset_at
+            .saturating_add(EPOCH_DURATION)
+            .le(&clock.unix_timestamp)
+    }
+
     async fn get_cutoff(&self, proof: Proof, buffer_time: u64) -> u64 {
         let clock = get_clock(&self.rpc_client).await;
         proof
@@ -212,6 +591,28 @@ impl Miner {
     }
 }
 
+// Picks a distinct core for each thread, reporting which ones were used
+pub fn pin_cores(threads: u64) -> Vec<CoreId> {
+    let mut core_ids = core_affinity::get_core_ids().unwrap_or_default();
+    core_ids.truncate(threads as usize);
+    if core_ids.is_empty() {
+        println!(
+            "{} CPU affinity is not supported on this system",
+            "WARNING".bold().yellow()
+        );
+    } else {
+        println!(
+            "Pinning mining threads to cores: {}",
+            core_ids
+                .iter()
+                .map(|core_id| core_id.id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    core_ids
+}
+
 fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
     1.0 + (balance as f64 / top_balance as f64).min(1.0f64)
 }
@@ -219,5 +620,5 @@ fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
 // TODO Pick a better strategy (avoid draining bus)
 fn find_bus() -> Pubkey {
     let i = rand::thread_rng().gen_range(0..BUS_COUNT);
-    BUS_ADDRESSES[i]
+    bus_addresses()[i]
 }
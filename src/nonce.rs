@@ -0,0 +1,38 @@
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    nonce_utils::{self, nonblocking::data_from_account},
+};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use crate::Miner;
+
+impl Miner {
+    // Returns the durable nonce to sign with, if a nonce account is set, or the latest
+    // blockhash, along with the slot it was observed at
+    pub async fn get_signing_hash(&self, client: &RpcClient) -> ClientResult<(Hash, u64)> {
+        match &self.nonce_account {
+            Some(nonce_account) => get_nonce(client, nonce_account).await,
+            None => self.get_latest_blockhash(client).await,
+        }
+    }
+}
+
+// Reads the current value of a nonce account. It changes each time a transaction using it lands.
+async fn get_nonce(client: &RpcClient, nonce_account: &Pubkey) -> ClientResult<(Hash, u64)> {
+    let response = client
+        .get_account_with_commitment(nonce_account, client.commitment())
+        .await?;
+    let nonce = response
+        .value
+        .ok_or(nonce_utils::Error::Client(format!(
+            "Nonce account {} does not exist",
+            nonce_account
+        )))
+        .and_then(|account| data_from_account(&account).map(|data| data.blockhash()))
+        .map_err(|err| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(format!("Invalid nonce account: {}", err)),
+        })?;
+    Ok((nonce, response.context.slot))
+}
@@ -1,12 +1,16 @@
 use solana_sdk::signature::Signer;
 
-use crate::{send_and_confirm::ComputeBudget, utils::proof_pubkey, Miner};
+use crate::{
+    send_request::ComputeBudget,
+    utils::{exit_on_error, proof_pubkey},
+    Miner,
+};
 
 impl Miner {
     pub async fn open(&self) {
         // Return early if miner is already registered
-        let signer = self.signer();
-        let fee_payer = self.fee_payer();
+        let signer = exit_on_error(self.signer());
+        let fee_payer = exit_on_error(self.fee_payer());
         let proof_address = proof_pubkey(signer.pubkey());
         if self.rpc_client.get_account(&proof_address).await.is_ok() {
             return;
@@ -15,7 +19,7 @@ impl Miner {
         // Sign and send transaction.
         println!("Generating challenge...");
         let ix = ore_api::instruction::open(signer.pubkey(), signer.pubkey(), fee_payer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+        self.send_request(&[ix], ComputeBudget::Dynamic, false)
             .await
             .ok();
     }
@@ -0,0 +1,105 @@
+use std::sync::OnceLock;
+
+use ore_api::consts::{
+    BUS, BUS_ADDRESSES, BUS_COUNT, CONFIG, CONFIG_ADDRESS, METADATA, METADATA_ADDRESS, MINT,
+    MINT_ADDRESS, MINT_NOISE, PROOF, TREASURY, TREASURY_ADDRESS, TREASURY_TOKENS_ADDRESS,
+};
+use solana_program::{instruction::Instruction, pubkey, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// Set once at startup by --program-id
+static PROGRAM_ID: OnceLock<Pubkey> = OnceLock::new();
+
+pub fn set_program_id(program_id: Pubkey) {
+    let _ = PROGRAM_ID.set(program_id);
+}
+
+/// The ORE program to use, ore_api::ID unless overridden with --program-id.
+pub fn program_id() -> Pubkey {
+    *PROGRAM_ID.get().unwrap_or(&ore_api::ID)
+}
+
+fn is_overridden() -> bool {
+    program_id() != ore_api::ID
+}
+
+pub fn config_address() -> Pubkey {
+    if !is_overridden() {
+        return CONFIG_ADDRESS;
+    }
+    Pubkey::find_program_address(&[CONFIG], &program_id()).0
+}
+
+pub fn bus_addresses() -> [Pubkey; BUS_COUNT] {
+    if !is_overridden() {
+        return BUS_ADDRESSES;
+    }
+    std::array::from_fn(|i| Pubkey::find_program_address(&[BUS, &[i as u8]], &program_id()).0)
+}
+
+pub fn mint_address() -> Pubkey {
+    if !is_overridden() {
+        return MINT_ADDRESS;
+    }
+    Pubkey::find_program_address(&[MINT, &MINT_NOISE], &program_id()).0
+}
+
+pub fn treasury_address() -> Pubkey {
+    if !is_overridden() {
+        return TREASURY_ADDRESS;
+    }
+    Pubkey::find_program_address(&[TREASURY], &program_id()).0
+}
+
+fn metadata_address() -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            METADATA,
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint_address().as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+// The ore_api instruction builders always target the mainnet program and its accounts. Swaps
+// them for the overriding deployment's, including the proofs of the instruction's signers.
+pub fn retarget(mut ix: Instruction) -> Instruction {
+    if !is_overridden() || ix.program_id != ore_api::ID {
+        return ix;
+    }
+    ix.program_id = program_id();
+    let signers: Vec<Pubkey> = ix
+        .accounts
+        .iter()
+        .filter(|account| account.is_signer)
+        .map(|account| account.pubkey)
+        .collect();
+    let buses = bus_addresses();
+    for account in ix.accounts.iter_mut() {
+        let address = account.pubkey;
+        account.pubkey = if address == CONFIG_ADDRESS {
+            config_address()
+        } else if let Some(i) = BUS_ADDRESSES.iter().position(|bus| *bus == address) {
+            buses[i]
+        } else if address == MINT_ADDRESS {
+            mint_address()
+        } else if address == TREASURY_ADDRESS {
+            treasury_address()
+        } else if address == TREASURY_TOKENS_ADDRESS {
+            get_associated_token_address(&treasury_address(), &mint_address())
+        } else if address == METADATA_ADDRESS {
+            metadata_address()
+        } else if let Some(signer) = signers.iter().find(|signer| {
+            Pubkey::find_program_address(&[PROOF, signer.as_ref()], &ore_api::ID).0 == address
+        }) {
+            Pubkey::find_program_address(&[PROOF, signer.as_ref()], &program_id()).0
+        } else {
+            address
+        };
+    }
+    ix
+}
@@ -0,0 +1,71 @@
+use std::{io, mem, sync::Mutex};
+
+use indicatif::{ProgressDrawTarget, TermLike};
+
+// Wide enough that status lines are never wrapped
+const WIDTH: u16 = 1024;
+
+/// Draw target for `--quiet` that writes progress as plain lines, without the cursor movement
+/// and redraws of the spinner. A line is written only when it changes.
+pub fn plain_draw_target() -> ProgressDrawTarget {
+    ProgressDrawTarget::term_like(Box::<PlainLines>::default())
+}
+
+#[derive(Debug, Default)]
+struct PlainLines {
+    pending: Mutex<String>,
+    last_drawn: Mutex<Vec<String>>,
+}
+
+impl TermLike for PlainLines {
+    fn width(&self) -> u16 {
+        WIDTH
+    }
+
+    fn move_cursor_up(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.push_str(s);
+        pending.push('\n');
+        Ok(())
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        self.pending.lock().unwrap().push_str(s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Each flush is a full redraw, so skip the lines that were already on screen last time
+    fn flush(&self) -> io::Result<()> {
+        let drawn: Vec<String> = mem::take(&mut *self.pending.lock().unwrap())
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let mut last_drawn = self.last_drawn.lock().unwrap();
+        for line in drawn.iter().filter(|line| !last_drawn.contains(line)) {
+            println!("{}", line);
+        }
+        *last_drawn = drawn;
+        Ok(())
+    }
+}
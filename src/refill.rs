@@ -0,0 +1,94 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use futures::future::BoxFuture;
+use solana_program::native_token::sol_to_lamports;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+use crate::{
+    cu_limits::CU_LIMIT_TRANSFER, send_request::ComputeBudget, utils::load_keypair, Miner,
+};
+
+// Window the --max-refills cap applies to
+const REFILL_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Miner {
+    // Tops up the fee payer from the refill wallet, returning whether a transfer landed.
+    // Boxed because it submits through send_request, which calls back into it.
+    pub fn refill_fee_payer<'a>(&'a self, fee_payer: &'a Keypair) -> BoxFuture<'a, bool> {
+        Box::pin(async move {
+            let Some(source) = &self.refill_keypair else {
+                return false;
+            };
+
+            // The refill transfer must not trigger another refill
+            if self.refilling.swap(true, Ordering::Relaxed) {
+                return false;
+            }
+            let landed = self.send_refill(source, fee_payer).await;
+            self.refilling.store(false, Ordering::Relaxed);
+            landed
+        })
+    }
+
+    async fn send_refill(&self, source: &str, fee_payer: &Keypair) -> bool {
+        {
+            let mut refills = self.refills.lock().unwrap();
+            refills.retain(|refilled_at| refilled_at.elapsed() < REFILL_PERIOD);
+            if refills.len() >= self.max_refills {
+                println!(
+                    "{}: Skipping fee payer refill, already refilled {} time(s) in the last 24 hours",
+                    "WARNING".bold().yellow(),
+                    refills.len()
+                );
+                return false;
+            }
+        }
+        let funder = match load_keypair(source) {
+            Ok(funder) => funder,
+            Err(err) => {
+                println!(
+                    "{}: Failed to load refill keypair: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+                return false;
+            }
+        };
+
+        println!(
+            "Refilling fee payer {} with {} SOL from {}",
+            fee_payer.pubkey(),
+            self.refill_amount,
+            funder.pubkey()
+        );
+        let ix = system_instruction::transfer(
+            &funder.pubkey(),
+            &fee_payer.pubkey(),
+            sol_to_lamports(self.refill_amount),
+        );
+        match self
+            .send_request_with_keys(
+                &[ix],
+                ComputeBudget::Fixed(CU_LIMIT_TRANSFER),
+                false,
+                &funder,
+                &funder,
+            )
+            .await
+        {
+            Ok(outcome) => {
+                println!("Refilled fee payer: {}", outcome.signature);
+                self.refills.lock().unwrap().push(Instant::now());
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
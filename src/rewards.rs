@@ -1,28 +1,95 @@
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use reqwest::Client;
+use serde_json::Value;
+use solana_sdk::signature::Signer;
+
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    args::RewardsArgs,
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_config, load_keypair, proof_pubkey},
     Miner,
 };
 
 impl Miner {
-    pub async fn rewards(&self) {
+    pub async fn rewards(&self, args: RewardsArgs) {
         let config = get_config(&self.rpc_client).await;
         let base_reward_rate = config.base_reward_rate;
 
+        // Value rewards only if a price is available
+        let price = match &args.price_source {
+            Some(url) => fetch_price(&self.http_client, url, &args.quote).await,
+            None => None,
+        };
+        let value = |amount: u64| match price {
+            Some(price) => format!(
+                " ({:.4} {})",
+                amount_u64_to_f64(amount) * price,
+                args.quote.to_uppercase()
+            ),
+            None => String::new(),
+        };
+
         let mut s = format!(
-            "{}: {} ORE",
+            "{}: {} ORE{}",
             config.min_difficulty,
-            amount_u64_to_string(base_reward_rate)
+            amount_u64_to_string(base_reward_rate),
+            value(base_reward_rate)
         )
         .to_string();
         for i in 1..32 {
             let reward_rate = base_reward_rate.saturating_mul(2u64.saturating_pow(i));
             s = format!(
-                "{}\n{}: {} ORE",
+                "{}\n{}: {} ORE{}",
                 s,
                 config.min_difficulty as u32 + i,
-                amount_u64_to_string(reward_rate)
+                amount_u64_to_string(reward_rate),
+                value(reward_rate)
             );
         }
         println!("{}", s);
+
+        // Show the signer's own rewards alongside their value
+        if price.is_some() {
+            let Some(keypair_filepath) = &self.keypair_filepath else {
+                return;
+            };
+            let Ok(signer) = load_keypair(keypair_filepath) else {
+                return;
+            };
+            if let Ok(data) = self
+                .rpc_client
+                .get_account_data(&proof_pubkey(signer.pubkey()))
+                .await
+            {
+                if let Ok(proof) = Proof::try_from_bytes(&data) {
+                    println!(
+                        "\nPending: {} ORE{}\nLifetime: {} ORE{}",
+                        amount_u64_to_string(proof.balance),
+                        value(proof.balance),
+                        amount_u64_to_string(proof.total_rewards),
+                        value(proof.total_rewards)
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Returns None if the price could not be fetched, so callers can omit values
+async fn fetch_price(client: &Client, url: &str, quote: &str) -> Option<f64> {
+    let response: Value = client.get(url).send().await.ok()?.json().await.ok()?;
+    find_price(&response, quote)
+}
+
+// Looks for a number keyed by the quote currency (e.g. {"ore":{"usd":1.0}}), or by "price"
+fn find_price(value: &Value, quote: &str) -> Option<f64> {
+    match value {
+        Value::Object(map) => map
+            .get(quote)
+            .or_else(|| map.get("price"))
+            .and_then(|price| price.as_f64())
+            .or_else(|| map.values().find_map(|value| find_price(value, quote))),
+        Value::Array(values) => values.iter().find_map(|value| find_price(value, quote)),
+        _ => None,
     }
 }
@@ -0,0 +1,91 @@
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use reqwest_rpc::{
+    header::{HeaderName, HeaderValue},
+    Proxy,
+};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_client::RpcClientConfig};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+const PROXY_CONNECT_TIMEOUT: u64 = 5;
+
+// Builds an RPC client that attaches the given headers to every request, e.g. for providers
+// that authenticate with an API key header, and routes requests through the proxy, if set
+pub fn new_rpc_client(
+    url: String,
+    timeout: Duration,
+    commitment: CommitmentConfig,
+    headers: &[(String, String)],
+    proxy: Option<&str>,
+) -> RpcClient {
+    if headers.is_empty() && proxy.is_none() {
+        return RpcClient::new_with_timeout_and_commitment(url, timeout, commitment);
+    }
+    let mut default_headers = HttpSender::default_headers();
+    for (name, value) in headers {
+        // Validated when the flags are parsed. Sensitive values are redacted from debug output.
+        let mut value = HeaderValue::from_str(value).unwrap();
+        value.set_sensitive(true);
+        default_headers.insert(HeaderName::from_bytes(name.as_bytes()).unwrap(), value);
+    }
+    let mut builder = reqwest_rpc::Client::builder()
+        .default_headers(default_headers)
+        .timeout(timeout)
+        .pool_idle_timeout(timeout);
+    if let Some(proxy) = proxy {
+        // Validated by check_proxy at startup
+        builder = builder.proxy(Proxy::all(proxy).unwrap());
+    }
+    let client = builder.build().expect("Failed to build RPC client");
+    RpcClient::new_sender(
+        HttpSender::new_with_client(url, client),
+        RpcClientConfig::with_commitment(commitment),
+    )
+}
+
+// Builds the client for requests outside the RPC, like dynamic fees and Jito bundles
+pub fn new_http_client(proxy: Option<&str>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy).unwrap());
+    }
+    builder.build().expect("Failed to build HTTP client")
+}
+
+// Checks that a proxy URL is well formed and accepts connections
+pub fn check_proxy(proxy: &str) -> Result<(), String> {
+    // Messages show the proxy without its user:pass@, and not at all if it fails to parse
+    let url = reqwest::Url::parse(proxy).map_err(|err| err.to_string())?;
+    let mut shown = url.clone();
+    let _ = shown.set_username("");
+    let _ = shown.set_password(None);
+    let default_port = match url.scheme() {
+        "http" => 80,
+        "https" => 443,
+        "socks5" | "socks5h" => 1080,
+        scheme => {
+            return Err(format!(
+                "Unsupported proxy scheme `{}`. Use http, https, socks5 or socks5h.",
+                scheme
+            ))
+        }
+    };
+    Proxy::all(proxy).map_err(|err| format!("`{}` {}", shown, err))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("`{}` has no host", shown))?;
+    let port = url.port().unwrap_or(default_port);
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|err| format!("Failed to resolve proxy {}: {}", host, err))?;
+    for addr in addrs {
+        if TcpStream::connect_timeout(&addr, Duration::from_secs(PROXY_CONNECT_TIMEOUT)).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(format!("Proxy {}:{} is unreachable", host, port))
+}
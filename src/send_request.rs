@@ -1,168 +1,866 @@
-use std::time::Duration;
+use std::{
+    fmt,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
+use base64::{prelude::BASE64_STANDARD, Engine};
 use colored::*;
+use futures::{future::join_all, StreamExt};
+use indicatif::ProgressBar;
+use ore_api::instruction::OreInstruction;
+use rand::{seq::SliceRandom, Rng};
 use solana_client::{
     client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    rpc_config::RpcSendTransactionConfig,
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{
+        RpcSendTransactionConfig, RpcSignatureSubscribeConfig, RpcSimulateTransactionConfig,
+        RpcTransactionConfig,
+    },
+    rpc_request::{RpcError, RpcResponseErrorData},
+    rpc_response::RpcSignatureResult,
 };
 use solana_program::{
+    address_lookup_table::AddressLookupTableAccount,
     instruction::Instruction,
     native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
 };
-use solana_rpc_client::spinner;
 use solana_sdk::{
-    commitment_config::CommitmentLevel,
+    commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
-    signature::{Signature, Signer},
-    transaction::Transaction,
+    hash::Hash,
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    signature::{Keypair, Signature, Signer},
+    system_instruction::{self, SystemInstruction},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{
+    TransactionConfirmationStatus, TransactionStatus, UiTransactionEncoding,
 };
-use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
-
-use crate::Miner;
 
-const MIN_SOL_BALANCE: f64 = 0.005;
+use crate::{
+    cu_limits::CU_LIMIT_ADVANCE_NONCE,
+    program::{program_id, retarget},
+    telemetry::SubmitEvent,
+    Miner,
+};
 
 const RPC_RETRIES: usize = 0;
-const _SIMULATION_RETRIES: usize = 4;
-const GATEWAY_RETRIES: usize = 150;
-const CONFIRM_RETRIES: usize = 1;
+pub const SIMULATION_RETRIES: usize = 4;
+pub const GATEWAY_RETRIES: usize = 150;
+pub const CONFIRM_RETRIES: usize = 5;
 
-const CONFIRM_DELAY: u64 = 0;
-const GATEWAY_DELAY: u64 = 300;
+pub const CONFIRM_DELAY: u64 = 400;
+pub const RPC_TIMEOUT: u64 = 30;
+pub const GATEWAY_DELAY: u64 = 300;
 
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+pub const CU_MARGIN_PERCENT: u8 = 10;
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const BLOCKHASH_REFRESH_SECS: u64 = 30;
+const WS_CONFIRM_TIMEOUT: u64 = 10;
+const MIN_CONFIRMATIONS_TIMEOUT: u64 = 60;
+const WAIT_FOR_FUNDS_INTERVAL: u64 = 5;
+const BLOCKHASH_NOT_FOUND_RETRIES: usize = 3;
+
+/// Compute unit limit for a transaction: simulated when `Dynamic`, or exactly the given units
+/// when `Fixed`. `--cu-limit` turns every budget into `Fixed`.
 pub enum ComputeBudget {
     Dynamic,
     Fixed(u32),
 }
 
-impl Miner {
+/// Result of a successful submission.
+pub struct SubmitOutcome {
+    pub signature: Signature,
+    /// Slot the transaction was confirmed in, when confirmation was awaited
+    pub landed_slot: Option<u64>,
+    /// Submission rounds it took, counting the first
+    pub attempts: usize,
+    /// Compute unit price and limit the final transaction was signed with, which may differ from
+    /// the initial ones after simulation and fee escalation. Zero for transactions signed offline.
+    pub priority_fee: u64,
+    pub cu_limit: u32,
+    /// Lamports paid for the transaction (base fee, priority fee, and any Jito tip), or zero
+    /// when it was not confirmed
+    pub fee: u64,
+}
+
+/// Why a submission did not land.
+#[derive(Debug)]
+pub enum SubmitError {
+    MaxRetries,
+    /// An error or confirmation budget ran out
+    BudgetExhausted(String),
+    Timeout,
+    /// The fee payer holds no more than the minimum balance, in lamports
+    InsufficientBalance {
+        balance: u64,
+        required: u64,
+    },
+    /// The transaction landed but failed
+    OnChain(TransactionError),
+    /// `--simulate-only` ran the transaction and it failed
+    SimulationFailed(TransactionError),
+    /// The solution was not worth submitting
+    SubmissionSkipped {
+        difficulty: u32,
+        min_difficulty: u32,
+    },
+    Interrupted,
+    /// The priority fee would cost more than --high-fee-threshold, in lamports
+    FeeTooHigh {
+        priority_fee: u64,
+        cu_limit: u32,
+        cost: u64,
+        threshold: u64,
+    },
+    /// The signed transaction does not fit in a packet, in bytes
+    TransactionTooLarge(usize),
+    /// A transaction file for offline signing could not be written, read, or verified
+    Offline(String),
+    /// The signer, fee payer, or nonce authority keypair could not be loaded
+    Keypair(String),
+    Rpc(ClientError),
+}
 
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubmitError::MaxRetries => write!(f, "Max retries"),
+            SubmitError::BudgetExhausted(message) => write!(f, "{}", message),
+            SubmitError::Timeout => write!(f, "Timed out"),
+            SubmitError::InsufficientBalance { balance, required } => write!(
+                f,
+                "Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
+                lamports_to_sol(*balance),
+                lamports_to_sol(*required)
+            ),
+            SubmitError::OnChain(err) => write!(f, "{}", err),
+            SubmitError::SimulationFailed(err) => write!(f, "Simulation failed: {}", err),
+            SubmitError::SubmissionSkipped {
+                difficulty,
+                min_difficulty,
+            } => write!(
+                f,
+                "SubmissionSkipped: best difficulty {} is below the minimum of {}",
+                difficulty, min_difficulty
+            ),
+            SubmitError::Interrupted => write!(f, "Interrupted"),
+            SubmitError::FeeTooHigh {
+                priority_fee,
+                cu_limit,
+                cost,
+                threshold,
+            } => write!(
+                f,
+                "Priority fee of {} microlamports/CU at a limit of {} CU would cost {} SOL, over the {} SOL threshold. Pass --allow-high-fee to send it anyway.",
+                priority_fee,
+                cu_limit,
+                lamports_to_sol(*cost),
+                lamports_to_sol(*threshold)
+            ),
+            SubmitError::TransactionTooLarge(size) => write!(
+                f,
+                "Transaction is {} bytes, over the {} byte limit. Send fewer instructions, or shrink it with an address lookup table (--lookup-table).",
+                size, PACKET_DATA_SIZE
+            ),
+            SubmitError::Offline(message) => write!(f, "{}", message),
+            SubmitError::Keypair(message) => write!(f, "{}", message),
+            SubmitError::Rpc(err) => write!(f, "{}", err),
+        }
+    }
+}
 
+impl std::error::Error for SubmitError {}
+
+impl From<ClientError> for SubmitError {
+    fn from(err: ClientError) -> Self {
+        SubmitError::Rpc(err)
+    }
+}
+
+impl Miner {
     pub async fn send_request(
         &self,
         ixs: &[Instruction],
         compute_budget: ComputeBudget,
         skip_confirm: bool,
-        best_diff: u32,
-    ) -> ClientResult<Signature> {
-        let progress_bar = spinner::new_progress_bar();
-        let signer = self.signer();
-        let client = self.rpc_client.clone();
-        let fee_payer = self.fee_payer();
-        
-	    
-        // Return error, if balance is zero
-        if let Ok(balance) = client.get_balance(&fee_payer.pubkey()).await {
-            if balance <= sol_to_lamports(MIN_SOL_BALANCE) {
-                panic!(
-                    "{} Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
-                    "ERROR".bold().red(),
-                    lamports_to_sol(balance),
-                    MIN_SOL_BALANCE
-                );
+    ) -> Result<SubmitOutcome, SubmitError> {
+        let signer = self.signer().map_err(SubmitError::Keypair)?;
+        let fee_payer = self.fee_payer().map_err(SubmitError::Keypair)?;
+        self.send_request_with_keys(ixs, compute_budget, skip_confirm, &signer, &fee_payer)
+            .await
+    }
+
+    // Like send_request, but signs and pays for the transaction with the given keys
+    pub async fn send_request_with_keys(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        signer: &Keypair,
+        fee_payer: &Keypair,
+    ) -> Result<SubmitOutcome, SubmitError> {
+        let start = Instant::now();
+        let ixs: Vec<Instruction> = ixs.iter().cloned().map(retarget).collect();
+        let result = self
+            .submit(&ixs, compute_budget, skip_confirm, signer, fee_payer)
+            .await;
+        if !self.dry_run && !self.simulate_only && self.dump_unsigned.is_none() {
+            self.record_submission(&ixs, &result, start);
+            match &result {
+                Ok(outcome) => self.emit(&SubmitEvent::Succeeded(outcome)),
+                Err(err) => self.emit(&SubmitEvent::Failed(err)),
             }
         }
+        result
+    }
 
-        // Set compute units
-        let mut final_ixs = vec![];
-        match compute_budget {
-            ComputeBudget::Dynamic => {
-                // TODO simulate
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(1_400_000))
+    async fn submit(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        signer: &Keypair,
+        fee_payer: &Keypair,
+    ) -> Result<SubmitOutcome, SubmitError> {
+        let progress_bar = self.new_progress_bar();
+        let mut client = self.active_client();
+
+        // Return error, if balance is zero. Skipped for wallets funded right before submission.
+        let mut balance = if self.balance_check {
+            client.get_balance(&fee_payer.pubkey()).await.ok()
+        } else {
+            None
+        };
+        if balance.map_or(false, |balance| {
+            balance <= sol_to_lamports(self.min_balance)
+        }) && self.refill_fee_payer(fee_payer).await
+        {
+            balance = client.get_balance(&fee_payer.pubkey()).await.ok();
+        }
+        if let Some(balance) = balance {
+            let required = sol_to_lamports(self.min_balance);
+            let balance = match self.wait_for_funds {
+                Some(timeout) if balance <= required => {
+                    self.wait_for_funds(&client, fee_payer, timeout, &progress_bar)
+                        .await?
+                }
+                _ => balance,
+            };
+            if balance <= required {
+                let err = SubmitError::InsufficientBalance { balance, required };
+                progress_bar.finish_with_message(format!("{} {}", "ERROR".bold().red(), err));
+                return Err(err);
             }
-            ComputeBudget::Fixed(cus) => {
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus))
+        }
+
+        // Set compute units. A user-supplied limit replaces both fixed and simulated budgets.
+        let compute_budget = match self.cu_limit {
+            Some(cus) => ComputeBudget::Fixed(cus),
+            None => compute_budget,
+        };
+        let mut cu_limit = match compute_budget {
+            ComputeBudget::Dynamic => MAX_COMPUTE_UNIT_LIMIT,
+            ComputeBudget::Fixed(cus) => cus,
+        };
+        let mut final_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(cu_limit)];
+
+        let priority_fee = match (self.command_priority_fee(ixs), &self.dynamic_fee_url) {
+            (Some(fee), _) => fee,
+            (None, Some(_)) => match self.dynamic_fee(ixs).await {
+                Ok(fee) => fee,
+                Err(err) => {
+                    // Keep submitting with the static fee rather than stalling on the provider
+                    let fee = self.priority_fee.unwrap_or(0);
+                    progress_bar.println(format!(
+                        "{} {}. Falling back to static priority fee of {}",
+                        "WARNING".bold().yellow(),
+                        err,
+                        fee
+                    ));
+                    fee
+                }
+            },
+            (None, None) => self.priority_fee.unwrap_or(0),
+        };
+        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
+            priority_fee,
+        ));
+        final_ixs.extend_from_slice(ixs);
+
+        // Tip Jito for bundle inclusion, spreading tips across the tip accounts. Addresses are
+        // validated when the flags are parsed.
+        if self.jito {
+            if let Some(tip_account) = self.jito_tip_accounts.choose(&mut rand::thread_rng()) {
+                final_ixs.push(system_instruction::transfer(
+                    &fee_payer.pubkey(),
+                    tip_account,
+                    self.jito_tip,
+                ));
             }
         }
 
-        let priority_fee = match &self.dynamic_fee_url {
-            Some(_) => {
-                self.dynamic_fee().await
+        // Simulate to size the compute unit limit, falling back to the max, or to the fixed
+        // limit with --simulate-compute-then-send
+        let simulate = match compute_budget {
+            ComputeBudget::Dynamic => true,
+            ComputeBudget::Fixed(_) => self.simulate_compute_then_send && self.cu_limit.is_none(),
+        };
+        if simulate {
+            progress_bar.set_message("Simulating transaction...");
+            if let Some(units_consumed) = self
+                .simulate(&final_ixs, &fee_payer.pubkey(), cu_limit)
+                .await
+            {
+                cu_limit = units_consumed
+                    .saturating_add(
+                        units_consumed.saturating_mul(self.cu_margin_percent as u64) / 100,
+                    )
+                    .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32;
+                final_ixs[0] = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
+                if self.simulate_compute_then_send {
+                    progress_bar.println(format!(
+                        "Simulation consumed {} compute units, using a limit of {}",
+                        units_consumed, cu_limit
+                    ));
+                }
             }
-            None => {
-                self.priority_fee.unwrap_or(0)
+        }
+
+        // Leave room for advancing the nonce
+        if self.nonce_account.is_some() {
+            cu_limit = cu_limit
+                .saturating_add(CU_LIMIT_ADVANCE_NONCE)
+                .min(MAX_COMPUTE_UNIT_LIMIT);
+            final_ixs[0] = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
+        }
+
+        // Raise the priority fee to the floor, if set
+        let priority_fee = match self.priority_fee_min {
+            Some(min_fee) if priority_fee < min_fee => {
+                progress_bar.println(format!(
+                    "Priority fee of {} is below the floor, using {} instead",
+                    priority_fee, min_fee
+                ));
+                min_fee
             }
+            _ => priority_fee,
         };
 
-        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee));
-        final_ixs.extend_from_slice(ixs);
+        // Cap the priority fee now that the compute unit limit is settled
+        let mut priority_fee = match self.max_priority_fee(cu_limit) {
+            Some(max_fee) if priority_fee > max_fee => {
+                progress_bar.println(format!(
+                    "{} Priority fee of {} exceeds the cap, using {} instead",
+                    "WARNING".bold().yellow(),
+                    priority_fee,
+                    max_fee
+                ));
+                max_fee
+            }
+            _ => priority_fee,
+        };
+        final_ixs[1] = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+
+        // Refuse fees that look mistyped
+        let cost = priority_fee_cost(priority_fee, cu_limit);
+        let threshold = sol_to_lamports(self.high_fee_threshold_sol);
+        if !self.allow_high_fee && cost > threshold {
+            let err = SubmitError::FeeTooHigh {
+                priority_fee,
+                cu_limit,
+                cost,
+                threshold,
+            };
+            progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), err));
+            return Err(err);
+        }
 
         // Build tx
-        let send_cfg = RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: Some(CommitmentLevel::Confirmed),
+        let mut send_cfg = RpcSendTransactionConfig {
+            skip_preflight: !self.preflight,
+            preflight_commitment: Some(self.rpc_client.commitment().commitment),
             encoding: Some(UiTransactionEncoding::Base64),
             max_retries: Some(RPC_RETRIES),
             min_context_slot: None,
         };
-        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
+        let lookup_tables = self.lookup_tables().await?;
 
-        // Sign tx
-        let (hash, _slot) = client
-            .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
-            .await
-            .unwrap();
+        // Sign tx. Nodes behind the slot the blockhash was fetched at reject it, if requested.
+        let (mut hash, slot) = self.get_signing_hash(&client).await?;
+        if self.use_min_context_slot {
+            send_cfg.min_context_slot = Some(slot);
+        }
+        let nonce_authority = self.nonce_authority().map_err(SubmitError::Keypair)?;
+        let nonce = self
+            .nonce_account
+            .as_ref()
+            .map(|account| (account, nonce_authority.as_ref().unwrap_or(fee_payer)));
 
-        
-        if signer.pubkey() == fee_payer.pubkey() {
-            tx.sign(&[&signer], hash);
-        } else {
-            tx.sign(&[&signer, &fee_payer], hash);
+        let mut tx = sign_tx(&final_ixs, signer, fee_payer, hash, &lookup_tables, nonce)?;
+        if let Err(err) = check_size(&tx) {
+            progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), err));
+            return Err(err);
+        }
+        let mut signed_at = Instant::now();
+        let mut signed_attempts = 0;
+        if self.verbose {
+            progress_bar.println(format!("{}: {}", "Signer".bold(), signer.pubkey()));
+            progress_bar.println(format!("{}: {}", "Fee payer".bold(), fee_payer.pubkey()));
+            for (i, ix) in final_ixs.iter().enumerate() {
+                progress_bar.println(format!(
+                    "  #{} {} {}",
+                    i,
+                    ix.program_id,
+                    describe_instruction(ix)
+                ));
+            }
+        }
+
+        // Print the transaction instead of sending it
+        if self.dry_run {
+            progress_bar.finish_and_clear();
+            println!(
+                "{}: {}",
+                "Message".bold(),
+                BASE64_STANDARD.encode(tx.message.serialize())
+            );
+            println!("{}: {}", "Compute unit limit".bold(), cu_limit);
+            println!("{}: {}", "Priority fee".bold(), priority_fee);
+            return Ok(SubmitOutcome {
+                signature: tx.signatures[0],
+                landed_slot: None,
+                attempts: 0,
+                priority_fee,
+                cu_limit,
+                fee: 0,
+            });
+        }
+
+        // Write the transaction out for offline signing instead of sending it
+        if let Some(path) = &self.dump_unsigned {
+            progress_bar.finish_and_clear();
+            let mut unsigned = tx.clone();
+            unsigned.signatures = vec![Signature::default(); unsigned.signatures.len()];
+            if let Err(err) = write_tx(path, &unsigned) {
+                println!("{}: {}", "ERROR".bold().red(), err);
+                return Err(err);
+            }
+            println!("Unsigned transaction written to {}", path);
+            let required_signers = tx.message.header().num_required_signatures as usize;
+            for signer in &tx.message.static_account_keys()[..required_signers] {
+                println!("{}: {}", "Required signer".bold(), signer);
+            }
+            println!("{}: {}", "Compute unit limit".bold(), cu_limit);
+            println!("{}: {}", "Priority fee".bold(), priority_fee);
+            return Ok(SubmitOutcome {
+                signature: Signature::default(),
+                landed_slot: None,
+                attempts: 0,
+                priority_fee,
+                cu_limit,
+                fee: 0,
+            });
+        }
+
+        // Run the signed transaction through the RPC simulator instead of sending it
+        if self.simulate_only {
+            progress_bar.set_message("Simulating transaction...");
+            let sim_res = self
+                .rpc_client
+                .simulate_transaction_with_config(
+                    &tx,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: true,
+                        replace_recent_blockhash: false,
+                        commitment: Some(self.rpc_client.commitment()),
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        accounts: None,
+                        min_context_slot: None,
+                        inner_instructions: false,
+                    },
+                )
+                .await;
+            progress_bar.finish_and_clear();
+            let sim_res = sim_res?.value;
+            for log in sim_res.logs.unwrap_or_default() {
+                println!("  {}", log);
+            }
+            println!("{}: {}", "Compute unit limit".bold(), cu_limit);
+            if let Some(units_consumed) = sim_res.units_consumed {
+                println!("{}: {}", "Units consumed".bold(), units_consumed);
+            }
+            println!("{}: {}", "Priority fee".bold(), priority_fee);
+            if let Some(err) = sim_res.err {
+                let err = SubmitError::SimulationFailed(err);
+                println!("{}: {}", "ERROR".bold().red(), err);
+                return Err(err);
+            }
+            println!("{} Simulation succeeded", "OK".bold().green());
+            return Ok(SubmitOutcome {
+                signature: tx.signatures[0],
+                landed_slot: None,
+                attempts: 0,
+                priority_fee,
+                cu_limit,
+                fee: 0,
+            });
         }
 
-	    
         // Submit tx
+        let clients: Vec<Arc<RpcClient>> = std::iter::once(self.rpc_client.clone())
+            .chain(self.rpc_fallbacks.iter().cloned())
+            .collect();
+        let mut client_index = self.active_rpc.load(Ordering::Relaxed) % clients.len();
+        let mut submit_failures = 0;
+        let mut last_sent: Option<Signature> = None;
+        let mut awaiting: Option<Signature> = None;
+        let mut sent: Vec<Signature> = vec![];
         let mut attempts = 0;
+        let mut balance_checked_at = Instant::now();
+        let mut submit_errors = 0;
+        let mut unconfirmed_rounds = 0;
+        let mut blockhash_retries = 0;
+        let mut blockhash_not_found = false;
+        let mut fee_requeries = 0;
+        let dynamic_fee =
+            self.dynamic_fee_url.is_some() && self.command_priority_fee(ixs).is_none();
+        let start = Instant::now();
         loop {
-            let message = match &self.dynamic_fee_url {
-                Some(_) => format!("Submitting transaction... (attempt {} with dynamic priority fee of {} via {})", attempts, priority_fee, self.dynamic_fee_strategy.as_ref().unwrap()),
-                None => format!("Submitting transaction... (attempt {} with static priority fee of {})", attempts, priority_fee),
+            // Stop retrying once the user asks to exit
+            if self.interrupted.load(Ordering::Relaxed) {
+                progress_bar.finish_with_message(format!("{}: Interrupted", "ERROR".bold().red()));
+                print_sent_signatures(&sent);
+                return Err(SubmitError::Interrupted);
+            }
+
+            // Give up once the deadline passes, regardless of remaining retries
+            if let Some(send_timeout) = self.send_timeout {
+                if start.elapsed().as_secs() >= send_timeout {
+                    progress_bar
+                        .finish_with_message(format!("{}: Timed out", "ERROR".bold().red()));
+                    print_sent_signatures(&sent);
+                    return Err(SubmitError::Timeout);
+                }
+            }
+
+            // Escalate the priority fee after repeated failures
+            let mut rebuilt = false;
+            if self.fee_escalation_attempts > 0
+                && attempts > 0
+                && attempts % self.fee_escalation_attempts == 0
+            {
+                let escalated_fee = (priority_fee as f64 * self.fee_escalation_multiplier) as u64;
+                let escalated_fee = self.cap_raised_fee(escalated_fee, cu_limit);
+                if escalated_fee > priority_fee {
+                    priority_fee = escalated_fee;
+                    final_ixs[1] = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+                    rebuilt = true;
+                }
+            }
+
+            // Congestion changes fast, so re-query the fee provider after a round that did not
+            // land, and switch to its fee if it is higher
+            if dynamic_fee
+                && attempts > 0
+                && awaiting.is_none()
+                && fee_requeries < self.dynamic_fee_requeries
+            {
+                fee_requeries += 1;
+                if let Ok(fresh_fee) = self.dynamic_fee(ixs).await {
+                    let fresh_fee = self.cap_raised_fee(
+                        fresh_fee.max(self.priority_fee_min.unwrap_or(0)),
+                        cu_limit,
+                    );
+                    if fresh_fee > priority_fee {
+                        priority_fee = fresh_fee;
+                        final_ixs[1] =
+                            ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+                        rebuilt = true;
+                    }
+                }
+            }
+
+            // Re-sign with a fresh blockhash before the current one can expire. A durable nonce
+            // never expires, so it is only re-read when the transaction has to change.
+            if blockhash_not_found {
+                self.expire_blockhash();
+            }
+            let expiring = nonce.is_none()
+                && (signed_at.elapsed().as_secs() >= BLOCKHASH_REFRESH_SECS
+                    || signed_attempts >= self.blockhash_refresh_attempts);
+            if rebuilt || blockhash_not_found || expiring {
+                match self.get_signing_hash(&client).await {
+                    Ok((latest_hash, slot)) => {
+                        hash = latest_hash;
+                        if self.use_min_context_slot {
+                            send_cfg.min_context_slot = Some(slot);
+                        }
+                        signed_at = Instant::now();
+                        signed_attempts = 0;
+                    }
+                    Err(err) => {
+                        progress_bar.set_message(format!(
+                            "{}: {}",
+                            "ERROR".bold().red(),
+                            err.kind()
+                        ));
+                    }
+                }
+                tx = sign_tx(&final_ixs, signer, fee_payer, hash, &lookup_tables, nonce)?;
+                awaiting = None;
+                blockhash_not_found = false;
+            }
+            signed_attempts += 1;
+
+            // Re-check the balance before sending, in case a concurrent transaction spent it
+            let low_balance = match awaiting {
+                None if attempts > 0 => {
+                    self.recheck_balance(&client, fee_payer, &mut balance_checked_at)
+                        .await
+                }
+                _ => None,
             };
 
-            progress_bar.set_message(message);
+            // Keep waiting on the signature in flight until it has to be re-signed
+            let result = if let Some(balance) = low_balance {
+                Err(balance)
+            } else if let Some(sig) = awaiting {
+                progress_bar.set_message(format!(
+                    "Waiting for confirmation... (attempt {})",
+                    attempts
+                ));
+                Ok(Ok(sig))
+            } else {
+                let message = match &self.dynamic_fee_url {
+                    Some(_) if self.command_priority_fee(ixs).is_none() => format!(
+                        "Submitting transaction... (attempt {} with dynamic priority fee of {} via {})",
+                        attempts,
+                        priority_fee,
+                        self.dynamic_fee_strategy.as_ref().unwrap()
+                    ),
+                    _ => format!(
+                        "Submitting transaction... (attempt {} with static priority fee of {})",
+                        attempts, priority_fee
+                    ),
+                };
+
+                progress_bar.set_message(message);
 
-            match client.send_transaction_with_config(&tx, send_cfg).await {
+                self.wait_for_submit_interval().await;
+                self.record(|metrics| {
+                    metrics.submissions.fetch_add(1, Ordering::Relaxed);
+                });
+                Ok(if self.spam_rpcs {
+                    self.broadcast_tx(&clients, &tx, send_cfg)
+                        .await
+                        .map(|(index, sig)| {
+                            client = clients[index].clone();
+                            sig
+                        })
+                } else {
+                    self.send_tx(&client, &tx, send_cfg).await
+                })
+            };
+
+            // Skip this attempt, leaving the fee payer a chance to be topped up
+            let result = match result {
+                Ok(result) => result,
+                Err(balance) => {
+                    let message = format!(
+                        "Fee payer balance dropped to {} SOL, skipping attempt {}",
+                        lamports_to_sol(balance),
+                        attempts
+                    );
+                    self.log_attempt(
+                        attempts,
+                        priority_fee,
+                        None,
+                        "skipped",
+                        Some(message.clone()),
+                        start,
+                    );
+                    progress_bar.set_message(format!("{}: {}", "WARNING".bold().yellow(), message));
+                    tokio::time::sleep(self.retry_delay(attempts, false)).await;
+                    attempts += 1;
+                    if attempts > self.max_retries {
+                        progress_bar
+                            .finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
+                        print_sent_signatures(&sent);
+                        return Err(SubmitError::InsufficientBalance {
+                            balance,
+                            required: sol_to_lamports(self.min_balance),
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            // A lagging RPC may not have seen the blockhash yet, so re-sign with its latest one
+            // and resend right away. This comes before confirming an earlier copy, which would
+            // otherwise take every BlockhashNotFound once a copy has been sent.
+            if let Err(err) = &result {
+                if self.retry_on_blockhash_not_found
+                    && blockhash_retries < BLOCKHASH_NOT_FOUND_RETRIES
+                    && is_blockhash_not_found(err)
+                {
+                    self.log_attempt(
+                        attempts,
+                        priority_fee,
+                        None,
+                        "error",
+                        Some(err.to_string()),
+                        start,
+                    );
+                    blockhash_retries += 1;
+                    blockhash_not_found = true;
+                    progress_bar.set_message("Blockhash not found. Re-signing...");
+                    continue;
+                }
+            }
+
+            // A rejected resubmission may mean an earlier copy already landed, so confirm that one
+            let result = match result {
+                Err(err) => match landed_signature(&err, &tx, last_sent) {
+                    Some(sig) => {
+                        self.log_attempt(
+                            attempts,
+                            priority_fee,
+                            Some(&sig),
+                            "duplicate",
+                            Some(err.to_string()),
+                            start,
+                        );
+                        Ok(sig)
+                    }
+                    None => Err(err),
+                },
+                result => result,
+            };
+            let rate_limited = result.as_ref().err().map_or(false, is_rate_limited);
+            match result {
                 Ok(sig) => {
+                    submit_failures = 0;
+                    last_sent = Some(sig);
+                    if !sent.contains(&sig) {
+                        sent.push(sig);
+                    }
+
                     // Skip confirmation
-                    if best_diff.lt(&20) {
-			println!("\nDifficulty: {} ,难度值小于20不提交!!!",best_diff);
+                    if skip_confirm || self.no_confirm {
+                        self.log_attempt(attempts, priority_fee, Some(&sig), "sent", None, start);
                         progress_bar.finish_with_message(format!("Sent: {}", sig));
-                        return Ok(sig);
+                        return Ok(SubmitOutcome {
+                            signature: sig,
+                            landed_slot: None,
+                            attempts: attempts + 1,
+                            priority_fee,
+                            cu_limit,
+                            fee: 0,
+                        });
+                    }
+
+                    // Wait for the node to push a confirmation, if subscribed
+                    if let Some(ws_url) = &self.ws_url {
+                        match self.confirm_by_subscription(ws_url, &sig).await {
+                            Some(Ok(slot)) => {
+                                if self.wait_for_depth(&client, &sig, &progress_bar).await {
+                                    let fee = self.charge_fee(&tx, cu_limit, priority_fee);
+                                    self.log_attempt(
+                                        attempts,
+                                        priority_fee,
+                                        Some(&sig),
+                                        "confirmed",
+                                        None,
+                                        start,
+                                    );
+                                    progress_bar.finish_with_message(confirmed_message(
+                                        &sig, start, attempts,
+                                    ));
+                                    return Ok(SubmitOutcome {
+                                        signature: sig,
+                                        landed_slot: Some(slot),
+                                        attempts: attempts + 1,
+                                        priority_fee,
+                                        cu_limit,
+                                        fee,
+                                    });
+                                }
+                            }
+                            Some(Err(err)) => {
+                                self.charge_fee(&tx, cu_limit, priority_fee);
+                                self.log_attempt(
+                                    attempts,
+                                    priority_fee,
+                                    Some(&sig),
+                                    "failed",
+                                    Some(err.to_string()),
+                                    start,
+                                );
+                                progress_bar.finish_with_message(format!(
+                                    "{}: {}",
+                                    "ERROR".bold().red(),
+                                    err
+                                ));
+                                print_sent_signatures(&sent);
+                                return Err(SubmitError::OnChain(err));
+                            }
+                            None => {}
+                        }
                     }
-              
 
                     // Confirm the tx landed
-                    for _ in 0..CONFIRM_RETRIES {
-                        std::thread::sleep(Duration::from_millis(CONFIRM_DELAY));
-                        match client.get_signature_statuses(&[sig]).await {
-                            Ok(signature_statuses) => {
-                                for status in signature_statuses.value {
-                                    if let Some(status) = status {
-                                        if let Some(err) = status.err {
-                                            progress_bar.finish_with_message(format!(
-                                                "{}: {}",
-                                                "ERROR".bold().red(),
-                                                err
-                                            ));
-                                            return Err(ClientError {
-                                                request: None,
-                                                kind: ClientErrorKind::Custom(err.to_string()),
-                                            });
-                                        }
-                                        if let Some(confirmation) = status.confirmation_status {
-                                            match confirmation {
-                                                TransactionConfirmationStatus::Processed => {}
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    progress_bar.finish_with_message(format!(
-                                                        "{} {}",
-                                                        "OK".bold().green(),
-                                                        sig
-                                                    ));
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        }
+                    for _ in 0..self.confirm_retries {
+                        tokio::time::sleep(Duration::from_millis(self.confirm_delay_ms)).await;
+                        match self.get_status(&client, &sig).await {
+                            Ok(status) => {
+                                if let Some(status) = status {
+                                    if let Some(err) = status.err {
+                                        self.charge_fee(&tx, cu_limit, priority_fee);
+                                        self.log_attempt(
+                                            attempts,
+                                            priority_fee,
+                                            Some(&sig),
+                                            "failed",
+                                            Some(err.to_string()),
+                                            start,
+                                        );
+                                        progress_bar.finish_with_message(format!(
+                                            "{}: {}",
+                                            "ERROR".bold().red(),
+                                            err
+                                        ));
+                                        print_sent_signatures(&sent);
+                                        return Err(SubmitError::OnChain(err));
+                                    }
+                                    if self.reached_commitment(&status.confirmation_status)
+                                        && self.wait_for_depth(&client, &sig, &progress_bar).await
+                                    {
+                                        let fee = self.charge_fee(&tx, cu_limit, priority_fee);
+                                        self.log_attempt(
+                                            attempts,
+                                            priority_fee,
+                                            Some(&sig),
+                                            "confirmed",
+                                            None,
+                                            start,
+                                        );
+                                        progress_bar.finish_with_message(confirmed_message(
+                                            &sig, start, attempts,
+                                        ));
+                                        return Ok(SubmitOutcome {
+                                            signature: sig,
+                                            landed_slot: Some(status.slot),
+                                            attempts: attempts + 1,
+                                            priority_fee,
+                                            cu_limit,
+                                            fee,
+                                        });
                                     }
                                 }
                             }
@@ -172,33 +870,1213 @@ impl Miner {
                                 progress_bar.set_message(format!(
                                     "{}: {}",
                                     "ERROR".bold().red(),
-                                    err.kind().to_string()
+                                    err.kind()
                                 ));
                             }
                         }
                     }
+                    self.log_attempt(
+                        attempts,
+                        priority_fee,
+                        Some(&sig),
+                        "unconfirmed",
+                        None,
+                        start,
+                    );
+                    unconfirmed_rounds += 1;
+                    if self.on_unconfirmed == "wait" {
+                        awaiting = Some(sig);
+                    }
                 }
 
                 // Handle submit errors
                 Err(err) => {
-                    progress_bar.set_message(format!(
-                        "{}: {}",
-                        "ERROR".bold().red(),
-                        err.kind().to_string()
-                    ));
+                    self.log_attempt(
+                        attempts,
+                        priority_fee,
+                        None,
+                        "error",
+                        Some(err.to_string()),
+                        start,
+                    );
+
+                    // A failed preflight will fail the same way on every retry
+                    if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                        data: RpcResponseErrorData::SendTransactionPreflightFailure(_),
+                        ..
+                    }) = err.kind()
+                    {
+                        progress_bar.finish_with_message(format!(
+                            "{}: {}",
+                            "ERROR".bold().red(),
+                            err
+                        ));
+                        print_sent_signatures(&sent);
+                        return Err(SubmitError::Rpc(err));
+                    }
+                    progress_bar.set_message(format!("{}: {}", "ERROR".bold().red(), err.kind()));
+
+                    // Rotate to the next RPC after repeated failures
+                    submit_errors += 1;
+                    submit_failures += 1;
+                    if submit_failures >= self.rpc_fallback_threshold && clients.len() > 1 {
+                        client_index = (client_index + 1) % clients.len();
+                        client = clients[client_index].clone();
+                        self.active_rpc.store(client_index, Ordering::Relaxed);
+                        submit_failures = 0;
+                    }
                 }
             }
 
+            // Give up once either budget is spent, naming the one that ran out
+            let exhausted = if submit_errors > self.max_submit_errors {
+                Some(format!(
+                    "Submit error budget exhausted after {} failed submissions",
+                    submit_errors
+                ))
+            } else if unconfirmed_rounds > self.max_unconfirmed_rounds {
+                Some(format!(
+                    "Confirmation budget exhausted after {} unconfirmed submissions",
+                    unconfirmed_rounds
+                ))
+            } else {
+                None
+            };
+            if let Some(message) = exhausted {
+                progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), message));
+                print_sent_signatures(&sent);
+                return Err(SubmitError::BudgetExhausted(message));
+            }
+
             // Retry
-            std::thread::sleep(Duration::from_millis(GATEWAY_DELAY));
+            // Honor any Retry-After from the block engine. The RPC client already waits out
+            // Retry-After itself before surfacing a 429.
+            let mut delay = self.retry_delay(attempts, rate_limited);
+            if let Some(retry_at) = self.retry_after.lock().unwrap().take() {
+                delay = delay.max(retry_at.saturating_duration_since(Instant::now()));
+            }
+            tokio::time::sleep(delay).await;
             attempts += 1;
-            if attempts > GATEWAY_RETRIES {
+            self.record(|metrics| {
+                metrics.retries.fetch_add(1, Ordering::Relaxed);
+            });
+            if attempts > self.max_retries {
                 progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
+                print_sent_signatures(&sent);
+                return Err(SubmitError::MaxRetries);
+            }
+        }
+    }
+
+    // Sends a transaction signed offline as-is, resending it until it confirms or its blockhash
+    // expires
+    pub async fn submit_signed(&self, path: &str) -> Result<SubmitOutcome, SubmitError> {
+        let progress_bar = self.new_progress_bar();
+        let tx = match read_signed_tx(path) {
+            Ok(tx) => tx,
+            Err(err) => {
+                progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), err));
+                return Err(err);
+            }
+        };
+        let client = self.active_client();
+        let send_cfg = RpcSendTransactionConfig {
+            skip_preflight: !self.preflight,
+            preflight_commitment: Some(client.commitment().commitment),
+            encoding: Some(UiTransactionEncoding::Base64),
+            max_retries: Some(RPC_RETRIES),
+            min_context_slot: None,
+        };
+        let start = Instant::now();
+        let mut attempts = 0;
+        loop {
+            if self.interrupted.load(Ordering::Relaxed) {
+                progress_bar.finish_with_message(format!("{}: Interrupted", "ERROR".bold().red()));
+                return Err(SubmitError::Interrupted);
             }
+
+            // The transaction cannot be re-signed, so stop once its blockhash expires
+            if let Ok(false) = client
+                .is_blockhash_valid(tx.message.recent_blockhash(), client.commitment())
+                .await
+            {
+                let err = SubmitError::Offline(
+                    "Blockhash expired before the transaction confirmed. Sign a fresh one.".into(),
+                );
+                progress_bar.finish_with_message(format!("{}: {}", "ERROR".bold().red(), err));
+                return Err(err);
+            }
+
+            progress_bar.set_message(format!(
+                "Submitting signed transaction... (attempt {})",
+                attempts
+            ));
+            let result = match self.send_tx(&client, &tx, send_cfg).await {
+                Err(err) => landed_signature(&err, &tx, Some(tx.signatures[0])).ok_or(err),
+                result => result,
+            };
+            match result {
+                Ok(sig) => {
+                    for _ in 0..self.confirm_retries {
+                        tokio::time::sleep(Duration::from_millis(self.confirm_delay_ms)).await;
+                        let Ok(status) = self.get_status(&client, &sig).await else {
+                            continue;
+                        };
+                        if let Some(status) = status {
+                            if let Some(err) = status.err {
+                                progress_bar.finish_with_message(format!(
+                                    "{}: {}",
+                                    "ERROR".bold().red(),
+                                    err
+                                ));
+                                return Err(SubmitError::OnChain(err));
+                            }
+                            if self.reached_commitment(&status.confirmation_status)
+                                && self.wait_for_depth(&client, &sig, &progress_bar).await
+                            {
+                                let fee = match &tx.message {
+                                    VersionedMessage::Legacy(message) => {
+                                        client.get_fee_for_message(message).await
+                                    }
+                                    VersionedMessage::V0(message) => {
+                                        client.get_fee_for_message(message).await
+                                    }
+                                }
+                                .unwrap_or(0);
+                                self.record_fee(fee);
+                                progress_bar
+                                    .finish_with_message(confirmed_message(&sig, start, attempts));
+                                return Ok(SubmitOutcome {
+                                    signature: sig,
+                                    landed_slot: Some(status.slot),
+                                    attempts: attempts + 1,
+                                    priority_fee: 0,
+                                    cu_limit: 0,
+                                    fee,
+                                });
+                            }
+                        }
+                    }
+                }
+                Err(err) => {
+                    progress_bar.set_message(format!("{}: {}", "ERROR".bold().red(), err.kind()));
+                }
+            }
+
+            tokio::time::sleep(self.retry_delay(attempts, false)).await;
+            attempts += 1;
+            if attempts > self.max_retries {
+                progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
+                return Err(SubmitError::MaxRetries);
+            }
+        }
+    }
+
+    // The priority fee set for the kind of ORE instruction in the transaction, if any
+    fn command_priority_fee(&self, ixs: &[Instruction]) -> Option<u64> {
+        ixs.iter()
+            .filter(|ix| ix.program_id == program_id())
+            .find_map(
+                |ix| match OreInstruction::try_from(*ix.data.first()?).ok()? {
+                    OreInstruction::Mine => self.mine_priority_fee,
+                    OreInstruction::Claim => self.claim_priority_fee,
+                    OreInstruction::Stake => self.stake_priority_fee,
+                    _ => None,
+                },
+            )
+    }
+
+    // Fetches the status of a signature, or None if the node has not seen it yet. With
+    // --confirm-via gettransaction, a fetched transaction counts as reaching --confirm-commitment.
+    async fn get_status(
+        &self,
+        client: &RpcClient,
+        sig: &Signature,
+    ) -> ClientResult<Option<TransactionStatus>> {
+        if self.confirm_via != "gettransaction" {
+            let statuses = client.get_signature_statuses(&[*sig]).await?;
+            return Ok(statuses.value.into_iter().next().flatten());
+        }
+
+        // Transactions can't be fetched at processed commitment
+        let commitment = if self.confirm_commitment.is_finalized() {
+            CommitmentConfig::finalized()
+        } else {
+            CommitmentConfig::confirmed()
+        };
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        let Ok(tx) = client.get_transaction_with_config(sig, config).await else {
+            // Not found yet
+            return Ok(None);
+        };
+        let err = tx.transaction.meta.and_then(|meta| meta.err);
+        Ok(Some(TransactionStatus {
+            slot: tx.slot,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(if commitment.is_finalized() {
+                TransactionConfirmationStatus::Finalized
+            } else {
+                TransactionConfirmationStatus::Confirmed
+            }),
+        }))
+    }
+
+    // Polls a confirmed transaction until it is buried under --min-confirmations slots. Returns
+    // false if it dropped out of the chain or did not get deep enough in time.
+    async fn wait_for_depth(
+        &self,
+        client: &RpcClient,
+        sig: &Signature,
+        progress_bar: &ProgressBar,
+    ) -> bool {
+        if self.min_confirmations == 0 {
+            return true;
+        }
+        let start = Instant::now();
+        while start.elapsed().as_secs() < MIN_CONFIRMATIONS_TIMEOUT
+            && !self.interrupted.load(Ordering::Relaxed)
+        {
+            if let Ok(statuses) = client.get_signature_statuses(&[*sig]).await {
+                let Some(Some(status)) = statuses.value.first() else {
+                    return false;
+                };
+                let depth = statuses.context.slot.saturating_sub(status.slot);
+                if depth >= self.min_confirmations {
+                    return true;
+                }
+                progress_bar.set_message(format!(
+                    "Waiting for {} confirmations... ({} so far)",
+                    self.min_confirmations, depth
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(self.confirm_delay_ms)).await;
+        }
+        false
+    }
+
+    // Whether a signature status satisfies the commitment required by --confirm-commitment.
+    // Statuses beyond the required level count too.
+    pub fn reached_commitment(&self, status: &Option<TransactionConfirmationStatus>) -> bool {
+        let reached = match status {
+            Some(TransactionConfirmationStatus::Processed) => 0,
+            Some(TransactionConfirmationStatus::Confirmed) => 1,
+            Some(TransactionConfirmationStatus::Finalized) => 2,
+            None => return false,
+        };
+        reached >= commitment_rank(self.confirm_commitment)
+    }
+
+    // Adds a landed transaction's cost to the session total and returns it in lamports
+    fn charge_fee(&self, tx: &VersionedTransaction, cu_limit: u32, priority_fee: u64) -> u64 {
+        let base_fee = LAMPORTS_PER_SIGNATURE * tx.signatures.len() as u64;
+        let priority_lamports =
+            (cu_limit as u128 * priority_fee as u128).div_ceil(1_000_000) as u64;
+        let tip = if self.jito { self.jito_tip } else { 0 };
+        let fee = base_fee + priority_lamports + tip;
+        if self.verbose {
+            println!(
+                "  Fee breakdown: {} base + {} priority + {} tip = {} lamports",
+                base_fee, priority_lamports, tip, fee
+            );
         }
+        self.record_fee(fee);
+        fee
+    }
+
+    // Adds to this wallet's fee total, and to the fleet's when mining several wallets
+    fn record_fee(&self, fee: u64) {
+        self.fees_paid.fetch_add(fee, Ordering::Relaxed);
+        if let Some(fleet_fees_paid) = &self.fleet_fees_paid {
+            fleet_fees_paid.fetch_add(fee, Ordering::Relaxed);
+        }
+    }
+
+    // The RPC submissions go through: the primary, or the fallback the last rotation moved to
+    pub fn active_client(&self) -> Arc<RpcClient> {
+        let index = self.active_rpc.load(Ordering::Relaxed);
+        match index.checked_sub(1) {
+            Some(fallback) => self
+                .rpc_fallbacks
+                .get(fallback)
+                .unwrap_or(&self.rpc_client)
+                .clone(),
+            None => self.rpc_client.clone(),
+        }
+    }
+
+    // Polls the fee payer's balance until it rises above the minimum, returning the last balance
+    // seen once it does or the timeout passes
+    async fn wait_for_funds(
+        &self,
+        client: &RpcClient,
+        fee_payer: &Keypair,
+        timeout: u64,
+        progress_bar: &ProgressBar,
+    ) -> Result<u64, SubmitError> {
+        let required = sol_to_lamports(self.min_balance);
+        let start = Instant::now();
+        loop {
+            let balance = client.get_balance(&fee_payer.pubkey()).await.unwrap_or(0);
+            if balance > required || start.elapsed().as_secs() >= timeout {
+                return Ok(balance);
+            }
+            progress_bar.set_message(format!(
+                "{}: Fee payer {} holds {} SOL. Waiting for more than {} SOL... ({}s)",
+                "WARNING".bold().yellow(),
+                fee_payer.pubkey(),
+                lamports_to_sol(balance),
+                self.min_balance,
+                start.elapsed().as_secs()
+            ));
+            tokio::time::sleep(Duration::from_secs(WAIT_FOR_FUNDS_INTERVAL)).await;
+            if self.interrupted.load(Ordering::Relaxed) {
+                progress_bar.finish_with_message(format!("{}: Interrupted", "ERROR".bold().red()));
+                return Err(SubmitError::Interrupted);
+            }
+        }
+    }
+
+    // Returns the fee payer's balance if it fell to the minimum, checking at most once per
+    // --balance-recheck-interval
+    async fn recheck_balance(
+        &self,
+        client: &RpcClient,
+        fee_payer: &Keypair,
+        checked_at: &mut Instant,
+    ) -> Option<u64> {
+        let interval = self.balance_recheck_interval?;
+        if checked_at.elapsed().as_secs() < interval {
+            return None;
+        }
+        *checked_at = Instant::now();
+        let balance = client.get_balance(&fee_payer.pubkey()).await.ok()?;
+        (balance <= sol_to_lamports(self.min_balance)).then_some(balance)
+    }
+
+    // Highest compute unit price allowed by the microlamport and per-transaction SOL caps
+    fn max_priority_fee(&self, cu_limit: u32) -> Option<u64> {
+        let sol_cap = self
+            .max_priority_fee_per_tx_sol
+            .map(|sol| (sol_to_lamports(sol) as u128 * 1_000_000 / cu_limit.max(1) as u128) as u64);
+        match (self.priority_fee_max, sol_cap) {
+            (Some(max_fee), Some(sol_cap)) => Some(max_fee.min(sol_cap)),
+            (max_fee, sol_cap) => max_fee.or(sol_cap),
+        }
+    }
+
+    // Caps a fee raised mid-submission by the same limits as the initial fee, so raising it
+    // never trips --high-fee-threshold
+    fn cap_raised_fee(&self, fee: u64, cu_limit: u32) -> u64 {
+        let fee = self
+            .max_priority_fee(cu_limit)
+            .map_or(fee, |max_fee| fee.min(max_fee));
+        match self.allow_high_fee {
+            true => fee,
+            false => fee.min(self.high_fee_limit(cu_limit)),
+        }
+    }
+
+    // Highest priority fee whose cost stays within --high-fee-threshold
+    fn high_fee_limit(&self, cu_limit: u32) -> u64 {
+        (sol_to_lamports(self.high_fee_threshold_sol) as u128 * 1_000_000 / cu_limit.max(1) as u128)
+            as u64
+    }
+
+    // Spaces submissions out by at least the configured interval across the whole session,
+    // and staggers the first one so a fleet restarting together doesn't submit in lockstep
+    async fn wait_for_submit_interval(&self) {
+        let interval = Duration::from_millis(self.min_submit_interval_ms);
+        let elapsed = self
+            .last_submission
+            .lock()
+            .unwrap()
+            .map(|last| last.elapsed());
+        match elapsed {
+            Some(elapsed) if elapsed < interval => {
+                tokio::time::sleep(interval - elapsed).await;
+            }
+            None if self.startup_jitter_ms > 0 => {
+                let jitter = rand::thread_rng().gen_range(0..=self.startup_jitter_ms);
+                tokio::time::sleep(Duration::from_millis(jitter)).await;
+            }
+            _ => {}
+        }
+        *self.last_submission.lock().unwrap() = Some(Instant::now());
+    }
+
+    fn log_attempt(
+        &self,
+        attempt: usize,
+        priority_fee: u64,
+        sig: Option<&Signature>,
+        status: &str,
+        err: Option<String>,
+        start: Instant,
+    ) {
+        self.record(|metrics| match status {
+            "confirmed" => {
+                metrics.confirmations.fetch_add(1, Ordering::Relaxed);
+                metrics
+                    .confirmation_latency
+                    .observe(start.elapsed().as_secs_f64());
+                metrics.priority_fee.observe(priority_fee as f64);
+            }
+            "failed" | "error" => {
+                metrics.failures.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        });
+        let fee_strategy = match &self.dynamic_fee_url {
+            Some(_) => self.dynamic_fee_strategy.as_deref().unwrap_or("static"),
+            None => "static",
+        };
+        self.emit(&SubmitEvent::Attempt {
+            attempt,
+            priority_fee,
+            fee_strategy,
+            signature: sig,
+            status,
+            error: err.as_deref(),
+            elapsed: start.elapsed(),
+        });
+    }
+
+    // Reports a submission event to the telemetry hook, if set
+    fn emit(&self, event: &SubmitEvent) {
+        if let Some(telemetry) = &self.telemetry {
+            telemetry(event);
+        }
+    }
+
+    async fn send_tx(
+        &self,
+        client: &RpcClient,
+        tx: &VersionedTransaction,
+        send_cfg: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        // Fall back to the RPC if the block engine or leaders are unreachable
+        if self.jito {
+            if let Ok(sig) = self.send_bundle(tx).await {
+                return Ok(sig);
+            }
+        }
+        if let Some(tpu_client) = &self.tpu_client {
+            if let Ok(sig) = self.send_tpu(tpu_client, tx).await {
+                return Ok(sig);
+            }
+        }
+        client.send_transaction_with_config(tx, send_cfg).await
+    }
+
+    // Sends the same signed transaction to every RPC, returning the first one that accepted it
+    async fn broadcast_tx(
+        &self,
+        clients: &[Arc<RpcClient>],
+        tx: &VersionedTransaction,
+        send_cfg: RpcSendTransactionConfig,
+    ) -> ClientResult<(usize, Signature)> {
+        let results = join_all(
+            clients
+                .iter()
+                .map(|client| client.send_transaction_with_config(tx, send_cfg)),
+        )
+        .await;
+        let mut last_err = None;
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(sig) => return Ok((index, sig)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom("No RPC endpoints configured".into()),
+        }))
+    }
+
+    // Returns the confirmed slot, or None if the subscription could not be established or timed out
+    async fn confirm_by_subscription(
+        &self,
+        ws_url: &str,
+        sig: &Signature,
+    ) -> Option<Result<u64, TransactionError>> {
+        let pubsub_client = PubsubClient::new(ws_url).await.ok()?;
+        let (mut notifications, unsubscribe) = pubsub_client
+            .signature_subscribe(
+                sig,
+                Some(RpcSignatureSubscribeConfig {
+                    commitment: Some(self.confirm_commitment),
+                    enable_received_notification: Some(false),
+                }),
+            )
+            .await
+            .ok()?;
+        let notification = tokio::time::timeout(
+            Duration::from_secs(WS_CONFIRM_TIMEOUT),
+            notifications.next(),
+        )
+        .await;
+        drop(notifications);
+        unsubscribe().await;
+        match notification {
+            Ok(Some(response)) => match response.value {
+                RpcSignatureResult::ProcessedSignature(result) => match result.err {
+                    Some(err) => Some(Err(err)),
+                    None => Some(Ok(response.context.slot)),
+                },
+                RpcSignatureResult::ReceivedSignature(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Exponential backoff from the base delay up to the cap, with up to half of it jittered. After a
+    // 429 it is scaled past the cap by --rate-limit-backoff-multiplier, since retrying sooner only
+    // extends the limit
+    fn retry_delay(&self, attempts: usize, rate_limited: bool) -> Duration {
+        let mut delay = (self.retry_delay_ms as f64
+            * self.retry_delay_multiplier.powi(attempts as i32))
+        .min(self.retry_delay_max_ms as f64);
+        if rate_limited {
+            delay *= self.rate_limit_backoff_multiplier;
+        }
+        let delay = delay as u64;
+        let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+        Duration::from_millis(delay - delay / 2 + jitter)
+    }
+
+    // Returns the compute units the transaction consumes, or None if simulation fails and the
+    // fallback limit should be kept
+    async fn simulate(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        fallback_limit: u32,
+    ) -> Option<u64> {
+        let tx = Transaction::new_with_payer(ixs, Some(payer));
+        let mut attempts = 0;
+        let sim_res = loop {
+            match self
+                .rpc_client
+                .simulate_transaction_with_config(
+                    &tx,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: false,
+                        replace_recent_blockhash: true,
+                        commitment: Some(self.rpc_client.commitment()),
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        accounts: None,
+                        min_context_slot: None,
+                        inner_instructions: false,
+                    },
+                )
+                .await
+            {
+                Ok(sim_res) => break sim_res,
+                // Busy RPCs fail simulations transiently, so try again before giving up
+                Err(_) if attempts < self.simulation_retries => {
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(self.retry_delay_ms)).await;
+                }
+                Err(err) => {
+                    println!(
+                        "{}: Simulation failed after {} attempt(s), using a compute unit limit of {}: {}",
+                        "WARNING".bold().yellow(),
+                        attempts + 1,
+                        fallback_limit,
+                        err
+                    );
+                    return None;
+                }
+            }
+        };
+        if let Some(err) = sim_res.value.err {
+            if self.simulate_compute_then_send {
+                println!(
+                    "{}: Simulation failed, using a compute unit limit of {}: {}",
+                    "WARNING".bold().yellow(),
+                    fallback_limit,
+                    err
+                );
+            }
+            return None;
+        }
+        sim_res.value.units_consumed
+    }
+}
+
+// Returns the signature worth confirming when a submission was rejected as a duplicate:
+// the current one if it was already processed, or the last accepted one if its blockhash expired.
+fn landed_signature(
+    err: &ClientError,
+    tx: &VersionedTransaction,
+    last_sent: Option<Signature>,
+) -> Option<Signature> {
+    match err.get_transaction_error() {
+        Some(TransactionError::AlreadyProcessed) => Some(tx.signatures[0]),
+        Some(TransactionError::BlockhashNotFound) => last_sent,
+        _ if err.to_string().contains("already been processed") => Some(tx.signatures[0]),
+        _ => None,
+    }
+}
+
+// Writes a transaction as base64-encoded bincode, the format read back by --submit-signed
+fn write_tx(path: &str, tx: &VersionedTransaction) -> Result<(), SubmitError> {
+    let data = bincode::serialize(tx)
+        .map_err(|err| SubmitError::Offline(format!("Failed to serialize transaction: {}", err)))?;
+    std::fs::write(path, BASE64_STANDARD.encode(data))
+        .map_err(|err| SubmitError::Offline(format!("Failed to write {}: {}", path, err)))
+}
+
+// Reads a transaction written by --dump-unsigned and signed offline, checking that every
+// required signature is present and valid
+fn read_signed_tx(path: &str) -> Result<VersionedTransaction, SubmitError> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|err| SubmitError::Offline(format!("Failed to read {}: {}", path, err)))?;
+    let tx: VersionedTransaction = BASE64_STANDARD
+        .decode(data.trim())
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .ok_or_else(|| SubmitError::Offline(format!("{} is not a valid transaction", path)))?;
+    let required_signers = tx.message.header().num_required_signatures as usize;
+    if tx.signatures.len() != required_signers
+        || tx.signatures.iter().any(|sig| *sig == Signature::default())
+    {
+        return Err(SubmitError::Offline(format!(
+            "{} is missing signatures",
+            path
+        )));
+    }
+    if !tx.verify_with_results().into_iter().all(|valid| valid) {
+        return Err(SubmitError::Offline(format!(
+            "{} has an invalid signature",
+            path
+        )));
+    }
+    check_size(&tx)?;
+    Ok(tx)
+}
+
+// Rejects transactions the cluster would drop for not fitting in a packet
+fn check_size(tx: &VersionedTransaction) -> Result<(), SubmitError> {
+    let size = bincode::serialized_size(tx).unwrap_or(u64::MAX) as usize;
+    if size > PACKET_DATA_SIZE {
+        return Err(SubmitError::TransactionTooLarge(size));
+    }
+    Ok(())
+}
+
+// Orders commitment levels from processed up to finalized
+fn commitment_rank(commitment: CommitmentConfig) -> u8 {
+    if commitment.is_finalized() {
+        2
+    } else if commitment.is_at_least_confirmed() {
+        1
+    } else {
+        0
+    }
+}
+
+// Lists every signature sent for a transaction that failed, since an earlier attempt may still
+// have landed
+fn print_sent_signatures(sent: &[Signature]) {
+    if sent.is_empty() {
+        return;
+    }
+    println!(
+        "Sent {} signature{} for this transaction. Check each on an explorer, one may have landed:",
+        sent.len(),
+        if sent.len() == 1 { "" } else { "s" }
+    );
+    for sig in sent {
+        println!("  {}", sig);
+    }
+}
+
+fn confirmed_message(sig: &Signature, start: Instant, attempts: usize) -> String {
+    format!(
+        "{} {} (confirmed in {:.1}s after {} attempt{})",
+        "OK".bold().green(),
+        sig,
+        start.elapsed().as_secs_f64(),
+        attempts + 1,
+        if attempts == 0 { "" } else { "s" }
+    )
+}
+
+// Short human-readable summary of an instruction, for verbose logs
+fn describe_instruction(ix: &Instruction) -> String {
+    if ix.program_id == solana_sdk::compute_budget::id() {
+        return match (ix.data.first(), ix.data.get(1..)) {
+            (Some(2), Some(units)) if units.len() == 4 => format!(
+                "SetComputeUnitLimit {}",
+                u32::from_le_bytes(units.try_into().unwrap())
+            ),
+            (Some(3), Some(price)) if price.len() == 8 => format!(
+                "SetComputeUnitPrice {} microlamports/CU",
+                u64::from_le_bytes(price.try_into().unwrap())
+            ),
+            _ => "ComputeBudget".into(),
+        };
+    }
+    if ix.program_id == program_id() {
+        return match ix.data.first().map(|tag| OreInstruction::try_from(*tag)) {
+            Some(Ok(ore_ix)) => format!("Ore {:?}", ore_ix),
+            _ => "Ore (unknown)".into(),
+        };
+    }
+    if ix.program_id == solana_program::system_program::id() {
+        return match bincode::deserialize::<SystemInstruction>(&ix.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => {
+                format!(
+                    "Transfer {} lamports to {}",
+                    lamports, ix.accounts[1].pubkey
+                )
+            }
+            _ => "System".into(),
+        };
+    }
+    if ix.program_id == spl_associated_token_account::id() {
+        return "CreateAssociatedTokenAccount".into();
+    }
+    if ix.program_id == spl_token::id() {
+        return "Token".into();
+    }
+    "(unknown program)".into()
+}
+
+fn is_rate_limited(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Reqwest(err) => {
+            err.status().map_or(false, |status| status.as_u16() == 429)
+        }
+        ClientErrorKind::Custom(msg) => msg.contains("429"),
+        _ => false,
+    }
+}
+
+// Lamports a priority fee costs at the given compute unit limit
+fn priority_fee_cost(priority_fee: u64, cu_limit: u32) -> u64 {
+    (priority_fee as u128 * cu_limit as u128).div_ceil(1_000_000) as u64
+}
+
+fn is_blockhash_not_found(err: &ClientError) -> bool {
+    match err.get_transaction_error() {
+        Some(TransactionError::BlockhashNotFound) => true,
+        _ => err.to_string().contains("Blockhash not found"),
+    }
+}
+
+// Builds and signs a legacy transaction, or a v0 transaction when lookup tables are given
+fn sign_tx(
+    ixs: &[Instruction],
+    signer: &Keypair,
+    fee_payer: &Keypair,
+    hash: Hash,
+    lookup_tables: &[AddressLookupTableAccount],
+    nonce: Option<(&Pubkey, &Keypair)>,
+) -> ClientResult<VersionedTransaction> {
+    let mut signers = if signer.pubkey() == fee_payer.pubkey() {
+        vec![signer]
+    } else {
+        vec![signer, fee_payer]
+    };
+
+    // Advancing the nonce must come first, with the nonce in place of the blockhash
+    let mut ixs = ixs.to_vec();
+    if let Some((nonce_account, nonce_authority)) = nonce {
+        ixs.insert(
+            0,
+            system_instruction::advance_nonce_account(nonce_account, &nonce_authority.pubkey()),
+        );
+        if !signers
+            .iter()
+            .any(|signer| signer.pubkey() == nonce_authority.pubkey())
+        {
+            signers.push(nonce_authority);
+        }
+    }
+    let ixs = &ixs;
+    if lookup_tables.is_empty() {
+        let mut tx = Transaction::new_with_payer(ixs, Some(&fee_payer.pubkey()));
+        tx.sign(&signers, hash);
+        return Ok(tx.into());
+    }
+    let message =
+        v0::Message::try_compile(&fee_payer.pubkey(), ixs, lookup_tables, hash).map_err(|err| {
+            ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("Failed to compile v0 message: {}", err)),
+            }
+        })?;
+    VersionedTransaction::try_new(VersionedMessage::V0(message), &signers).map_err(|err| {
+        ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(format!("Failed to sign transaction: {}", err)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::{RpcError, RpcRequest, RpcResponseErrorData};
+    use solana_sdk::system_instruction;
+
+    use super::*;
+    use crate::test_utils::{keypair_file, scripted_client, test_miner, Script};
+
+    fn simulated_units(units_consumed: u64) -> ClientResult<Value> {
+        Ok(json!({
+            "context": { "slot": 1 },
+            "value": { "err": null, "logs": [], "unitsConsumed": units_consumed },
+        }))
+    }
+
+    fn node_error() -> ClientResult<Value> {
+        Err(RpcError::RpcResponseError {
+            code: -32005,
+            message: "Node is unhealthy".into(),
+            data: RpcResponseErrorData::NodeUnhealthy {
+                num_slots_behind: Some(100),
+            },
+        }
+        .into())
+    }
+
+    fn transfer_ix(miner: &Miner) -> Instruction {
+        let from = miner.signer().unwrap().pubkey();
+        system_instruction::transfer(&from, &Pubkey::new_unique(), 1)
+    }
+
+    #[tokio::test]
+    async fn sets_compute_unit_limit_from_simulation() {
+        let script = Arc::new(Script::default());
+        script.push(RpcRequest::SimulateTransaction, simulated_units(20_000));
+        let miner = test_miner(scripted_client(script.clone()));
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Dynamic, true)
+            .await
+            .unwrap();
+
+        // 20,000 simulated units plus the 10% margin
+        let sent = script.sent_transactions();
+        assert_eq!(
+            sent[0].message.instructions[0].data,
+            ComputeBudgetInstruction::set_compute_unit_limit(22_000).data
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_max_compute_unit_limit_without_simulated_units() {
+        let script = Arc::new(Script::default());
+        let miner = test_miner(scripted_client(script.clone()));
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Dynamic, true)
+            .await
+            .unwrap();
+        let sent = script.sent_transactions();
+        assert_eq!(
+            sent[0].message.instructions[0].data,
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT).data
+        );
+    }
+
+    #[test]
+    fn retry_delay_grows_up_to_the_cap() {
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.retry_delay_ms = 300;
+        miner.retry_delay_max_ms = 5_000;
+        miner.retry_delay_multiplier = 2.0;
+        for attempts in 0..10 {
+            // Jitter keeps each delay between half and all of the backoff
+            let backoff = (300 * 2u64.pow(attempts as u32)).min(5_000);
+            let delay = miner.retry_delay(attempts, false).as_millis() as u64;
+            assert!(
+                delay >= backoff - backoff / 2,
+                "attempt {}: {}ms",
+                attempts,
+                delay
+            );
+            assert!(delay <= backoff, "attempt {}: {}ms", attempts, delay);
+        }
+        assert!(miner.retry_delay(4, false) > miner.retry_delay(0, false));
+    }
+
+    #[test]
+    fn backs_off_longer_after_rate_limit() {
+        let mut miner = test_miner(scripted_client(Arc::default()));
+        miner.retry_delay_ms = 300;
+        miner.retry_delay_max_ms = 5_000;
+        miner.retry_delay_multiplier = 2.0;
+        miner.rate_limit_backoff_multiplier = 4.0;
+        let rate_limited: ClientError =
+            ClientErrorKind::Custom("HTTP status client error (429 Too Many Requests)".into())
+                .into();
+        let node_error = node_error().unwrap_err();
+        assert!(is_rate_limited(&rate_limited));
+        assert!(!is_rate_limited(&node_error));
+
+        // At the cap, a 429 waits at least half of 4 x 5s after jitter, a node error at most 5s
+        let delay = miner.retry_delay(10, is_rate_limited(&rate_limited));
+        assert!(delay >= Duration::from_millis(10_000), "{:?}", delay);
+        let delay = miner.retry_delay(10, is_rate_limited(&node_error));
+        assert!(delay <= Duration::from_millis(5_000), "{:?}", delay);
+    }
+
+    #[tokio::test]
+    async fn rotates_rpc_after_failure_threshold() {
+        let primary = Arc::new(Script::default());
+        let fallback = Arc::new(Script::default());
+        for _ in 0..3 {
+            primary.push(RpcRequest::SendTransaction, node_error());
+        }
+        let mut miner = test_miner(scripted_client(primary.clone()));
+        miner.rpc_fallbacks = vec![scripted_client(fallback.clone())];
+        miner.rpc_fallback_threshold = 2;
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        assert_eq!(primary.count(RpcRequest::SendTransaction), 2);
+        assert_eq!(fallback.count(RpcRequest::SendTransaction), 1);
+
+        // Later submissions and the blockhash refresher stay on the fallback
+        assert!(Arc::ptr_eq(&miner.active_client(), &miner.rpc_fallbacks[0]));
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        assert_eq!(primary.count(RpcRequest::SendTransaction), 2);
+        assert_eq!(fallback.count(RpcRequest::SendTransaction), 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_rpc_below_failure_threshold() {
+        let primary = Arc::new(Script::default());
+        let fallback = Arc::new(Script::default());
+        for _ in 0..2 {
+            primary.push(RpcRequest::SendTransaction, node_error());
+        }
+        let mut miner = test_miner(scripted_client(primary.clone()));
+        miner.rpc_fallbacks = vec![scripted_client(fallback.clone())];
+        miner.rpc_fallback_threshold = 3;
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        assert_eq!(primary.count(RpcRequest::SendTransaction), 3);
+        assert_eq!(fallback.count(RpcRequest::SendTransaction), 0);
+    }
+
+    #[tokio::test]
+    async fn retry_sleeps_let_other_submissions_run() {
+        // Both miners share one RPC, and every submission fails once
+        let script = Arc::new(Script::default());
+        for _ in 0..2 {
+            script.push(RpcRequest::SendTransaction, node_error());
+        }
+        let mut first = test_miner(scripted_client(script.clone()));
+        let mut second = test_miner(scripted_client(script.clone()));
+        for miner in [&mut first, &mut second] {
+            miner.retry_delay_ms = 20;
+            miner.retry_delay_max_ms = 20;
+        }
+        let (first_ixs, second_ixs) = ([transfer_ix(&first)], [transfer_ix(&second)]);
+        let (first_result, second_result) = tokio::join!(
+            first.send_request(&first_ixs, ComputeBudget::Fixed(10_000), true),
+            second.send_request(&second_ixs, ComputeBudget::Fixed(10_000), true),
+        );
+        first_result.unwrap();
+        second_result.unwrap();
+
+        // On a single-threaded runtime, a blocking sleep would hold the second miner back until
+        // the first one finished retrying
+        let payers: Vec<Pubkey> = script
+            .sent_transactions()
+            .iter()
+            .map(|tx| tx.message.account_keys[0])
+            .collect();
+        assert_eq!(payers.len(), 4);
+        assert_ne!(payers[0], payers[1]);
+    }
+
+    #[tokio::test]
+    async fn sends_with_fee_payer() {
+        let script = Arc::new(Script::default());
+        let mut miner = test_miner(scripted_client(script.clone()));
+        let fee_payer = Keypair::new();
+        miner.fee_payer_filepath = Some(keypair_file(&fee_payer));
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        let sent = &script.sent_transactions()[0];
+        assert_eq!(sent.message.account_keys[0], fee_payer.pubkey());
+        assert!(sent.verify_with_results().iter().all(|valid| *valid));
+    }
+
+    #[tokio::test]
+    async fn signs_once_when_signer_pays() {
+        let script = Arc::new(Script::default());
+        let miner = test_miner(scripted_client(script.clone()));
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        let sent = &script.sent_transactions()[0];
+        assert_eq!(
+            sent.message.account_keys[0],
+            miner.signer().unwrap().pubkey()
+        );
+        assert_eq!(sent.signatures.len(), 1);
+        assert!(sent.verify_with_results().iter().all(|valid| *valid));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn falls_back_to_static_fee_when_provider_times_out() {
+        // A fee provider that accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let _stream = listener.accept().await;
+            std::future::pending::<()>().await;
+        });
+
+        let script = Arc::new(Script::default());
+        let mut miner = test_miner(scripted_client(script.clone()));
+        miner.priority_fee = Some(5_000);
+        miner.dynamic_fee_url = Some(url);
+        miner.dynamic_fee_strategy = Some("helius".into());
+        let outcome = miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        assert_eq!(outcome.priority_fee, 5_000);
+        assert_eq!(
+            script.sent_transactions()[0].message.instructions[1].data,
+            ComputeBudgetInstruction::set_compute_unit_price(5_000).data
+        );
+    }
+
+    // Sends a transfer that the RPC first rejects for an unknown blockhash, returning how long
+    // the submission took and how many blockhashes it fetched
+    async fn send_after_blockhash_not_found(
+        retry_on_blockhash_not_found: bool,
+    ) -> (Duration, usize) {
+        let script = Arc::new(Script::default());
+        script.push(
+            RpcRequest::SendTransaction,
+            Err(ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound).into()),
+        );
+        let mut miner = test_miner(scripted_client(script.clone()));
+        miner.retry_on_blockhash_not_found = retry_on_blockhash_not_found;
+        miner.retry_delay_ms = 10_000;
+        miner.retry_delay_max_ms = 10_000;
+        let start = tokio::time::Instant::now();
+        miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), true)
+            .await
+            .unwrap();
+        assert_eq!(script.count(RpcRequest::SendTransaction), 2);
+        (
+            start.elapsed(),
+            script.count(RpcRequest::GetLatestBlockhash),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resigns_right_away_when_blockhash_not_found() {
+        let (elapsed, blockhashes) = send_after_blockhash_not_found(true).await;
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+        assert_eq!(blockhashes, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_on_blockhash_not_found_by_default() {
+        let (elapsed, blockhashes) = send_after_blockhash_not_found(false).await;
+        assert!(elapsed >= Duration::from_secs(5), "{:?}", elapsed);
+        assert_eq!(blockhashes, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resigns_when_resubmission_hits_blockhash_not_found() {
+        // The first copy is accepted but not confirmed, and its resubmission is rejected
+        let script = Arc::new(Script::default());
+        script.push(
+            RpcRequest::GetSignatureStatuses,
+            Ok(json!({ "context": { "slot": 1 }, "value": [null] })),
+        );
+        script.pass(RpcRequest::SendTransaction);
+        script.push(
+            RpcRequest::SendTransaction,
+            Err(ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound).into()),
+        );
+        let mut miner = test_miner(scripted_client(script.clone()));
+        miner.retry_on_blockhash_not_found = true;
+        miner.on_unconfirmed = "resubmit".into();
+        let outcome = miner
+            .send_request(&[transfer_ix(&miner)], ComputeBudget::Fixed(10_000), false)
+            .await
+            .unwrap();
+
+        // The rejection re-signs with a fresh blockhash instead of confirming the first copy
+        let sent = script.sent_transactions();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(script.count(RpcRequest::GetLatestBlockhash), 2);
+        assert_eq!(outcome.signature, sent[2].signatures[0]);
+    }
+
+    #[test]
+    fn rejects_transactions_over_packet_size() {
+        let signer = Keypair::new();
+        let transfers = |count| {
+            (0..count)
+                .map(|_| system_instruction::transfer(&signer.pubkey(), &Pubkey::new_unique(), 1))
+                .collect::<Vec<_>>()
+        };
+        let tx = sign_tx(
+            &transfers(1),
+            &signer,
+            &signer,
+            Hash::new_unique(),
+            &[],
+            None,
+        )
+        .unwrap();
+        assert!(check_size(&tx).is_ok());
+        let tx = sign_tx(
+            &transfers(40),
+            &signer,
+            &signer,
+            Hash::new_unique(),
+            &[],
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            check_size(&tx),
+            Err(SubmitError::TransactionTooLarge(size)) if size > PACKET_DATA_SIZE
+        ));
+    }
+
+    #[tokio::test]
+    async fn does_not_send_oversized_transactions() {
+        let script = Arc::new(Script::default());
+        let miner = test_miner(scripted_client(script.clone()));
+        let ixs: Vec<Instruction> = (0..40).map(|_| transfer_ix(&miner)).collect();
+        let result = miner
+            .send_request(&ixs, ComputeBudget::Fixed(10_000), true)
+            .await;
+        assert!(matches!(result, Err(SubmitError::TransactionTooLarge(_))));
+        assert_eq!(script.count(RpcRequest::SendTransaction), 0);
     }
 }
@@ -1,22 +1,34 @@
 use std::str::FromStr;
 
+use colored::*;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 
 use crate::{
-    args::StakeArgs, cu_limits::CU_LIMIT_CLAIM, send_and_confirm::ComputeBudget,
-    utils::amount_f64_to_u64, Miner,
+    args::StakeArgs,
+    cu_limits::CU_LIMIT_CLAIM,
+    program::mint_address,
+    send_request::ComputeBudget,
+    utils::{amount_f64_to_u64, amount_u64_to_string, exit_on_error, get_proof_with_authority},
+    Miner,
 };
 
 impl Miner {
     pub async fn stake(&self, args: StakeArgs) {
+        if let Some(path) = &args.submit_signed {
+            if self.submit_signed(path).await.is_err() {
+                std::process::exit(1);
+            }
+            return;
+        }
+
         // Get signer
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let sender = match args.sender {
             Some(sender) => Pubkey::from_str(&sender).expect("Failed to parse sender address"),
             None => spl_associated_token_account::get_associated_token_address(
                 &signer.pubkey(),
-                &ore_api::consts::MINT_ADDRESS,
+                &mint_address(),
             ),
         };
 
@@ -27,17 +39,42 @@ impl Miner {
         };
 
         // Parse amount
+        let balance = u64::from_str(token_account.token_amount.amount.as_str())
+            .expect("Failed to parse token balance");
         let amount: u64 = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
         } else {
-            u64::from_str(token_account.token_amount.amount.as_str())
-                .expect("Failed to parse token balance")
+            balance
         };
+        if amount > balance {
+            println!(
+                "{}: Cannot stake {} ORE, the token account only holds {} ORE",
+                "ERROR".bold().red(),
+                amount_u64_to_string(amount),
+                amount_u64_to_string(balance)
+            );
+            return;
+        }
 
         // Send tx
+        self.stake_tokens(sender, amount).await;
+    }
+
+    // Stakes tokens from the given token account, reporting the resulting stake
+    pub async fn stake_tokens(&self, sender: Pubkey, amount: u64) {
+        let signer = exit_on_error(self.signer());
         let ix = ore_api::instruction::stake(signer.pubkey(), sender, amount);
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+        match self
+            .send_request(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
             .await
-            .ok();
+        {
+            Ok(_) if self.simulate_only || self.dump_unsigned.is_some() => {}
+            Ok(_) => {
+                let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
+                println!("Total stake: {} ORE", amount_u64_to_string(proof.balance));
+            }
+            Err(_) if self.simulate_only || self.dump_unsigned.is_some() => std::process::exit(1),
+            Err(_) => {}
+        }
     }
 }
@@ -0,0 +1,39 @@
+use std::{fs, io};
+
+use serde_json::{json, Value};
+
+/// Best solution found so far for a proof challenge, persisted across restarts.
+pub struct MiningState {
+    pub challenge: [u8; 32],
+    pub nonce: u64,
+    pub difficulty: u32,
+}
+
+impl MiningState {
+    pub fn load(path: &str) -> Option<Self> {
+        let data = fs::read_to_string(path).ok()?;
+        let value: Value = serde_json::from_str(&data).ok()?;
+        let challenge = bs58::decode(value["challenge"].as_str()?)
+            .into_vec()
+            .ok()?
+            .try_into()
+            .ok()?;
+        Some(MiningState {
+            challenge,
+            nonce: value["nonce"].as_u64()?,
+            difficulty: value["difficulty"].as_u64()? as u32,
+        })
+    }
+
+    // Writes to a temp file first so a crash mid-write leaves the previous state intact
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let tmp_path = format!("{}.tmp", path);
+        let data = json!({
+            "challenge": bs58::encode(self.challenge).into_string(),
+            "nonce": self.nonce,
+            "difficulty": self.difficulty,
+        });
+        fs::write(&tmp_path, data.to_string())?;
+        fs::rename(tmp_path, path)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::Mutex,
+    time::Instant,
+};
+
+use chrono::Utc;
+use ore_api::instruction::OreInstruction;
+use solana_program::instruction::Instruction;
+
+use crate::{
+    program::program_id,
+    send_request::{SubmitError, SubmitOutcome},
+    Miner,
+};
+
+const HEADER: &str =
+    "timestamp,operation,signature,attempts,priority_fee,landed_slot,latency_secs,outcome";
+
+/// Append-only CSV log with one row per submission.
+pub struct StatsCsv {
+    file: Mutex<File>,
+}
+
+impl StatsCsv {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", HEADER)?;
+        }
+        Ok(StatsCsv {
+            file: Mutex::new(file),
+        })
+    }
+
+    fn append(&self, row: &[String]) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        let row: Vec<String> = row.iter().map(|field| escape(field)).collect();
+        writeln!(file, "{}", row.join(","))?;
+        file.flush()
+    }
+}
+
+impl Miner {
+    // Records the result of a submission, if a stats file is set. Failures to write are
+    // reported but never interrupt mining.
+    pub fn record_submission(
+        &self,
+        ixs: &[Instruction],
+        result: &Result<SubmitOutcome, SubmitError>,
+        start: Instant,
+    ) {
+        let Some(stats_csv) = &self.stats_csv else {
+            return;
+        };
+        let (signature, attempts, priority_fee, landed_slot, outcome) = match result {
+            Ok(outcome) => (
+                outcome.signature.to_string(),
+                outcome.attempts.to_string(),
+                outcome.priority_fee.to_string(),
+                outcome
+                    .landed_slot
+                    .map(|slot| slot.to_string())
+                    .unwrap_or_default(),
+                if outcome.landed_slot.is_some() {
+                    "confirmed".to_string()
+                } else {
+                    "sent".to_string()
+                },
+            ),
+            Err(err) => (
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                err.to_string(),
+            ),
+        };
+        let row = [
+            Utc::now().to_rfc3339(),
+            operation(ixs).to_string(),
+            signature,
+            attempts,
+            priority_fee,
+            landed_slot,
+            format!("{:.3}", start.elapsed().as_secs_f64()),
+            outcome,
+        ];
+        if let Err(err) = stats_csv.append(&row) {
+            self.println(format!("Failed to write stats: {}", err));
+        }
+    }
+}
+
+// Names a transaction by its last ORE instruction, since auth and reset precede the mine
+fn operation(ixs: &[Instruction]) -> String {
+    ixs.iter()
+        .rev()
+        .filter(|ix| ix.program_id == program_id())
+        .find_map(|ix| OreInstruction::try_from(*ix.data.first()?).ok())
+        .map_or("other".to_string(), |ore_ix| {
+            format!("{:?}", ore_ix).to_lowercase()
+        })
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,55 @@
+use std::{sync::Arc, time::Duration};
+
+use serde_json::json;
+use solana_sdk::signature::Signature;
+
+use crate::send_request::{SubmitError, SubmitOutcome};
+
+/// Called with every submission event. Runs inline in the submission loop, so it should return
+/// quickly and hand off any slow work.
+pub type TelemetryHook = Arc<dyn Fn(&SubmitEvent) + Send + Sync>;
+
+/// Progress of a transaction submission.
+pub enum SubmitEvent<'a> {
+    /// A submission round ended, e.g. with the transaction sent, confirmed or rejected
+    Attempt {
+        attempt: usize,
+        priority_fee: u64,
+        fee_strategy: &'a str,
+        signature: Option<&'a Signature>,
+        status: &'a str,
+        error: Option<&'a str>,
+        elapsed: Duration,
+    },
+    Succeeded(&'a SubmitOutcome),
+    Failed(&'a SubmitError),
+}
+
+// Prints one JSON object per attempt, for --log-format json
+pub fn json_logger() -> TelemetryHook {
+    Arc::new(|event| {
+        if let SubmitEvent::Attempt {
+            attempt,
+            priority_fee,
+            fee_strategy,
+            signature,
+            status,
+            error,
+            elapsed,
+        } = event
+        {
+            println!(
+                "{}",
+                json!({
+                    "signature": signature.map(|sig| sig.to_string()),
+                    "attempt": attempt,
+                    "priority_fee": priority_fee,
+                    "fee_strategy": fee_strategy,
+                    "status": status,
+                    "error": error,
+                    "elapsed_ms": elapsed.as_millis() as u64,
+                })
+            );
+        }
+    })
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde_json::Value;
+use solana_client::{
+    client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcRequest,
+};
+use solana_rpc_client::{
+    mock_sender::MockSender,
+    rpc_client::RpcClientConfig,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    task::JoinHandle,
+};
+
+use crate::{blockhash::BlockhashCache, Miner};
+
+/// Canned responses for an RPC, queued per request and answered in order. Requests without a
+/// queued response get the stock mock response.
+#[derive(Default)]
+pub struct Script {
+    responses: Mutex<HashMap<RpcRequest, VecDeque<Option<ClientResult<Value>>>>>,
+    calls: Mutex<Vec<(RpcRequest, Value)>>,
+}
+
+impl Script {
+    pub fn push(&self, request: RpcRequest, response: ClientResult<Value>) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(request)
+            .or_default()
+            .push_back(Some(response));
+    }
+
+    // Lets the next request through to the stock mock response, ahead of later queued ones
+    pub fn pass(&self, request: RpcRequest) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(request)
+            .or_default()
+            .push_back(None);
+    }
+
+    pub fn count(&self, request: RpcRequest) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(call, _)| *call == request)
+            .count()
+    }
+
+    // Transactions passed to sendTransaction, in the order they were sent
+    pub fn sent_transactions(&self) -> Vec<Transaction> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(call, _)| *call == RpcRequest::SendTransaction)
+            .map(|(_, params)| {
+                let data = BASE64_STANDARD.decode(params[0].as_str().unwrap()).unwrap();
+                bincode::deserialize(&data).unwrap()
+            })
+            .collect()
+    }
+}
+
+struct ScriptedSender {
+    mock: MockSender,
+    script: Arc<Script>,
+}
+
+#[async_trait]
+impl RpcSender for ScriptedSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        self.script
+            .calls
+            .lock()
+            .unwrap()
+            .push((request, params.clone()));
+        let response = self
+            .script
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(&request)
+            .and_then(|responses| responses.pop_front())
+            .flatten();
+        match response {
+            Some(response) => response,
+            None => self.mock.send(request, params).await,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "scripted".into()
+    }
+}
+
+pub fn scripted_client(script: Arc<Script>) -> Arc<RpcClient> {
+    Arc::new(RpcClient::new_sender(
+        ScriptedSender {
+            mock: MockSender::new("succeeds"),
+            script,
+        },
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    ))
+}
+
+// Answers one HTTP request with the given JSON, returning the server's URL and a handle to the
+// JSON body it was sent
+pub async fn serve_json(response: Value) -> (String, JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![];
+        let mut buf = [0u8; 4096];
+
+        // Read until the headers and the body they announce have arrived
+        let body = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed mid-request");
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).into_owned();
+            let Some(end) = text.find("\r\n\r\n") else {
+                continue;
+            };
+            let length = text[..end]
+                .lines()
+                .find_map(|line| {
+                    let line = line.to_ascii_lowercase();
+                    line.strip_prefix("content-length:")
+                        .map(|length| length.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                break request[end + 4..end + 4 + length].to_vec();
+            }
+        };
+        let response = response.to_string();
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    });
+    (url, handle)
+}
+
+// Writes a keypair to a temporary file, since the miner loads its keys by path
+pub fn keypair_file(keypair: &Keypair) -> String {
+    let path = std::env::temp_dir().join(format!("ore-cli-test-{}.json", keypair.pubkey()));
+    solana_sdk::signature::write_keypair_file(keypair, &path).unwrap();
+    path.display().to_string()
+}
+
+// A miner with quick retries and no balance checks, signing with a fresh keypair
+pub fn test_miner(rpc_client: Arc<RpcClient>) -> Miner {
+    let keypair_filepath = keypair_file(&Keypair::new());
+    Miner {
+        keypair_filepath: Some(keypair_filepath.clone()),
+        rpc_header_names: vec![],
+        http_client: reqwest::Client::new(),
+        priority_fee: None,
+        mine_priority_fee: None,
+        claim_priority_fee: None,
+        stake_priority_fee: None,
+        dynamic_fee_url: None,
+        dynamic_fee_strategy: None,
+        dynamic_fee_level: None,
+        dynamic_fee_percentile: 75,
+        dynamic_fee_max: None,
+        dynamic_fee_requeries: 0,
+        priority_fee_max: None,
+        priority_fee_min: None,
+        max_priority_fee_per_tx_sol: None,
+        high_fee_threshold_sol: 0.1,
+        allow_high_fee: false,
+        cu_limit: None,
+        cu_margin_percent: 10,
+        simulate_compute_then_send: false,
+        simulation_retries: 0,
+        fee_escalation_attempts: 0,
+        fee_escalation_multiplier: 1.0,
+        rpc_client,
+        blockhash_cache: Arc::new(BlockhashCache::default()),
+        rpc_fallbacks: vec![],
+        rpc_fallback_threshold: 3,
+        active_rpc: Arc::new(AtomicUsize::new(0)),
+        spam_rpcs: false,
+        ws_url: None,
+        fee_payer_filepath: Some(keypair_filepath),
+        nonce_account: None,
+        nonce_authority_filepath: None,
+        refill_keypair: None,
+        refill_amount: 0.0,
+        max_refills: 0,
+        refills: Arc::new(Mutex::new(vec![])),
+        refilling: Arc::new(AtomicBool::new(false)),
+        min_balance: 0.0,
+        balance_check: false,
+        wait_for_funds: None,
+        balance_recheck_interval: None,
+        preflight: false,
+        use_min_context_slot: false,
+        retry_on_blockhash_not_found: false,
+        no_confirm: false,
+        dry_run: false,
+        simulate_only: false,
+        dump_unsigned: None,
+        json_logs: false,
+        telemetry: None,
+        verbose: false,
+        quiet: true,
+        max_retries: 10,
+        max_submit_errors: 10,
+        max_unconfirmed_rounds: 10,
+        send_timeout: None,
+        min_submit_interval_ms: 0,
+        startup_jitter_ms: 0,
+        last_submission: Arc::new(Mutex::new(None)),
+        retry_after: Arc::new(Mutex::new(None)),
+        retry_delay_ms: 1,
+        retry_delay_max_ms: 1,
+        retry_delay_multiplier: 1.0,
+        rate_limit_backoff_multiplier: 1.0,
+        blockhash_refresh_attempts: 10,
+        confirm_retries: 1,
+        confirm_delay_ms: 1,
+        confirm_commitment: CommitmentConfig::confirmed(),
+        confirm_via: "getsignaturestatuses".into(),
+        min_confirmations: 0,
+        on_unconfirmed: "wait".into(),
+        jito: false,
+        jito_url: String::new(),
+        jito_tip: 0,
+        jito_tip_accounts: vec![],
+        lookup_table: None,
+        tpu_client: None,
+        metrics: None,
+        stats_csv: None,
+        interrupted: Arc::new(AtomicBool::new(false)),
+        fees_paid: Arc::new(AtomicU64::new(0)),
+        fleet_fees_paid: None,
+        label: None,
+        progress: None,
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use colored::*;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::{rpc_client::RpcClient, tpu_client::TpuClient},
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use crate::Miner;
+
+pub type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+// Returns None if the leader schedule or cluster nodes could not be fetched
+pub async fn connect_tpu(rpc_client: Arc<RpcClient>, ws_url: &str) -> Option<Arc<QuicTpuClient>> {
+    match TpuClient::new("ore-cli", rpc_client, ws_url, TpuClientConfig::default()).await {
+        Ok(tpu_client) => Some(Arc::new(tpu_client)),
+        Err(err) => {
+            println!(
+                "{}: Failed to connect to leader TPUs, sending through the RPC instead: {}",
+                "WARNING".bold().yellow(),
+                err
+            );
+            None
+        }
+    }
+}
+
+impl Miner {
+    pub async fn send_tpu(
+        &self,
+        tpu_client: &QuicTpuClient,
+        tx: &VersionedTransaction,
+    ) -> ClientResult<Signature> {
+        let wire_transaction = bincode::serialize(tx).map_err(|err| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(format!("Failed to serialize transaction: {}", err)),
+        })?;
+        tpu_client
+            .try_send_wire_transaction(wire_transaction)
+            .await
+            .map_err(|err| ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("TPU send failed: {}", err)),
+            })?;
+        Ok(tx.signatures[0])
+    }
+}
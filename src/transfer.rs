@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use colored::*;
+use ore_api::consts::TOKEN_DECIMALS;
+use solana_sdk::signature::Signer;
+use spl_token::amount_to_ui_amount;
+
+use crate::{
+    args::TransferArgs,
+    program::mint_address,
+    send_request::ComputeBudget,
+    utils::{amount_f64_to_u64, amount_u64_to_string, ask_confirm, exit_on_error},
+    Miner,
+};
+
+impl Miner {
+    pub async fn transfer(&self, args: TransferArgs) {
+        if let Some(path) = &args.submit_signed {
+            if self.submit_signed(path).await.is_err() {
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        // Check the sender holds enough
+        let signer = exit_on_error(self.signer());
+        let sender = spl_associated_token_account::get_associated_token_address(
+            &signer.pubkey(),
+            &mint_address(),
+        );
+        let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
+            println!("{}: You have no ORE token account", "ERROR".bold().red());
+            return;
+        };
+        let balance = u64::from_str(token_account.token_amount.amount.as_str())
+            .expect("Failed to parse token balance");
+        let amount = amount_f64_to_u64(args.amount);
+        if amount == 0 || amount > balance {
+            println!(
+                "{}: Cannot transfer {} ORE, the token account holds {} ORE",
+                "ERROR".bold().red(),
+                amount_u64_to_string(amount),
+                amount_u64_to_string(balance)
+            );
+            return;
+        }
+
+        // Create the recipient token account, if needed and allowed
+        let mut ixs = vec![];
+        let recipient =
+            spl_associated_token_account::get_associated_token_address(&args.to, &mint_address());
+        let recipient_funded = matches!(
+            self.rpc_client.get_token_account(&recipient).await,
+            Ok(Some(_))
+        );
+        if !recipient_funded {
+            if !args.allow_unfunded_recipient {
+                println!(
+                    "{}: {} has no ORE token account. Pass --allow-unfunded-recipient to create one, paying its rent.",
+                    "ERROR".bold().red(),
+                    args.to
+                );
+                return;
+            }
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &signer.pubkey(),
+                    &args.to,
+                    &mint_address(),
+                    &spl_token::id(),
+                ),
+            );
+        }
+        ixs.push(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &sender,
+                &mint_address(),
+                &recipient,
+                &signer.pubkey(),
+                &[],
+                amount,
+                TOKEN_DECIMALS,
+            )
+            .expect("Failed to build transfer instruction"),
+        );
+
+        // Confirm user wants to transfer
+        if !args.yes
+            && !ask_confirm(
+                format!(
+                    "\nYou are about to transfer {} to {}.\n\nAre you sure you want to continue? [Y/n]",
+                    format!("{} ORE", amount_to_ui_amount(amount, TOKEN_DECIMALS)).bold(),
+                    args.to
+                )
+                .as_str(),
+            )
+        {
+            return;
+        }
+
+        // Send tx
+        match self.send_request(&ixs, ComputeBudget::Dynamic, false).await {
+            Ok(_) if self.simulate_only || self.dump_unsigned.is_some() => {}
+            Ok(outcome) => {
+                println!("Transferred: {}", outcome.signature);
+                if let Ok(Some(token_account)) = self.rpc_client.get_token_account(&recipient).await
+                {
+                    println!(
+                        "Recipient balance: {} ORE",
+                        token_account.token_amount.ui_amount_string
+                    );
+                }
+            }
+            Err(_) => std::process::exit(1),
+        }
+    }
+}
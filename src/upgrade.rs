@@ -4,14 +4,15 @@ use spl_token::amount_to_ui_amount;
 
 use crate::{
     cu_limits::CU_LIMIT_UPGRADE,
-    send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64_v1, ask_confirm},
+    program::mint_address,
+    send_request::ComputeBudget,
+    utils::{amount_f64_to_u64_v1, ask_confirm, exit_on_error},
     Miner, UpgradeArgs,
 };
 
 impl Miner {
     pub async fn upgrade(&self, args: UpgradeArgs) {
-        let signer = &self.signer();
+        let signer = &exit_on_error(self.signer());
         let beneficiary = self.get_or_initialize_ata().await;
         let (sender, sender_balance) = self.get_ata_v1().await;
 
@@ -40,10 +41,10 @@ impl Miner {
 
         let ix = ore_api::instruction::upgrade(signer.pubkey(), beneficiary, sender, amount);
         match self
-            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
+            .send_request(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
             .await
         {
-            Ok(_sig) => {}
+            Ok(_outcome) => {}
             Err(err) => {
                 println!("error: {}", err);
             }
@@ -53,7 +54,7 @@ impl Miner {
     // asserts that token account exists and gets balance
     async fn get_ata_v1(&self) -> (Pubkey, f64) {
         // Initialize client.
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let client = self.rpc_client.clone();
 
         // Derive assoicated token address (for v1 account)
@@ -87,13 +88,13 @@ impl Miner {
 
     async fn get_or_initialize_ata(&self) -> Pubkey {
         // Initialize client
-        let signer = self.signer();
+        let signer = exit_on_error(self.signer());
         let client = self.rpc_client.clone();
 
         // Derive assoicated token address (ata)
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &mint_address(),
         );
 
         // Check if ata already exists or init
@@ -102,10 +103,10 @@ impl Miner {
             let ix = spl_associated_token_account::instruction::create_associated_token_account(
                 &signer.pubkey(),
                 &signer.pubkey(),
-                &ore_api::consts::MINT_ADDRESS,
+                &mint_address(),
                 &spl_token::id(),
             );
-            self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+            self.send_request(&[ix], ComputeBudget::Dynamic, false)
                 .await
                 .ok();
         }
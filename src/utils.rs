@@ -1,21 +1,32 @@
-use std::io::Read;
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
 
 use cached::proc_macro::cached;
+use chrono::{DateTime, Utc};
+use colored::*;
 use ore_api::{
-    consts::{
-        CONFIG_ADDRESS, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1, TREASURY_ADDRESS,
-    },
+    consts::{PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1},
     state::{Config, Proof, Treasury},
 };
 use ore_utils::AccountDeserialize;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::{pubkey::Pubkey, sysvar};
-use solana_sdk::clock::Clock;
+use solana_sdk::{
+    clock::Clock,
+    signature::{read_keypair, read_keypair_file, Keypair},
+};
 use spl_associated_token_account::get_associated_token_address;
 
+use crate::program::{config_address, mint_address, program_id, treasury_address};
+
 pub async fn _get_treasury(client: &RpcClient) -> Treasury {
     let data = client
-        .get_account_data(&TREASURY_ADDRESS)
+        .get_account_data(&treasury_address())
         .await
         .expect("Failed to get treasury account");
     *Treasury::try_from_bytes(&data).expect("Failed to parse treasury account")
@@ -23,7 +34,7 @@ pub async fn _get_treasury(client: &RpcClient) -> Treasury {
 
 pub async fn get_config(client: &RpcClient) -> Config {
     let data = client
-        .get_account_data(&CONFIG_ADDRESS)
+        .get_account_data(&config_address())
         .await
         .expect("Failed to get config account");
     *Config::try_from_bytes(&data).expect("Failed to parse config account")
@@ -81,10 +92,113 @@ pub fn ask_confirm(question: &str) -> bool {
 
 #[cached]
 pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
-    Pubkey::find_program_address(&[PROOF, authority.as_ref()], &ore_api::ID).0
+    Pubkey::find_program_address(&[PROOF, authority.as_ref()], &program_id()).0
 }
 
 #[cached]
 pub fn treasury_tokens_pubkey() -> Pubkey {
-    get_associated_token_address(&TREASURY_ADDRESS, &MINT_ADDRESS)
+    get_associated_token_address(&treasury_address(), &mint_address())
+}
+
+// Stdin can only be read once, so the keypair read from it is shared by every caller
+static STDIN_KEYPAIR: OnceLock<Result<Vec<u8>, String>> = OnceLock::new();
+
+/// Loads a keypair from a file path, `env:VAR` (JSON array or base58), or `-` for JSON on stdin.
+/// Errors never include the key material.
+pub fn load_keypair(source: &str) -> Result<Keypair, String> {
+    if source == "-" {
+        let bytes = STDIN_KEYPAIR.get_or_init(|| {
+            read_keypair(&mut std::io::stdin())
+                .map(|keypair| keypair.to_bytes().to_vec())
+                .map_err(|_| "No valid keypair found on stdin".to_string())
+        });
+        return bytes
+            .clone()
+            .and_then(|bytes| Keypair::from_bytes(&bytes).map_err(|err| err.to_string()));
+    }
+    if let Some(var) = source.strip_prefix("env:") {
+        let value =
+            std::env::var(var).map_err(|_| format!("Environment variable {} is not set", var))?;
+        let value = value.trim();
+        return if value.starts_with('[') {
+            read_keypair(&mut Cursor::new(value))
+                .map_err(|_| format!("Environment variable {} is not a valid keypair", var))
+        } else {
+            bs58::decode(value)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| Keypair::from_bytes(&bytes).ok())
+                .ok_or_else(|| format!("Environment variable {} is not a valid keypair", var))
+        };
+    }
+    // Name only files with an extension, in case a secret was passed where a path was expected
+    let path = Path::new(source);
+    read_keypair_file(source).map_err(|_| match (path.file_name(), path.extension()) {
+        (Some(name), Some(_)) => format!("Could not read keypair file {}", name.to_string_lossy()),
+        _ => "Could not read keypair file".to_string(),
+    })
+}
+
+// Prints the error and exits, for commands that cannot continue without a keypair
+pub fn exit_on_error<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{} {}", "ERROR".bold().red(), err);
+        std::process::exit(1);
+    })
+}
+
+// Parses a Retry-After header value, given either in seconds or as an HTTP date
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let retry_at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        retry_at
+            .with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+// Files in a directory, in a stable order, for commands that act on a directory of keypairs
+pub fn keypair_paths(dir: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+// Keeps the scheme and host of a URL for display, masking the userinfo, path and query that may
+// carry an API key
+pub fn redact_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "<redacted>".to_string();
+    };
+    let Some(host) = parsed.host_str() else {
+        return "<redacted>".to_string();
+    };
+    let origin = match parsed.port() {
+        Some(port) => format!("{}://{}:{}", parsed.scheme(), host, port),
+        None => format!("{}://{}", parsed.scheme(), host),
+    };
+    if parsed.path() == "/" && parsed.query().is_none() && parsed.fragment().is_none() {
+        origin
+    } else {
+        format!("{}/<redacted>", origin)
+    }
+}
+
+// Masks a URL wherever it shows up in an error, as reqwest errors include the request URL
+pub fn redact_url_in(message: &str, url: &str) -> String {
+    let redacted = redact_url(url);
+    let message = message.replace(url, &redacted);
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => message.replace(parsed.as_str(), &redacted),
+        Err(_) => message,
+    }
 }
@@ -0,0 +1,118 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use colored::*;
+use core_affinity::CoreId;
+use futures::future::join_all;
+use indicatif::MultiProgress;
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::signature::{read_keypair_file, Signer};
+
+use crate::{args::MineArgs, progress::plain_draw_target, utils::keypair_paths, Miner};
+
+impl Miner {
+    // Runs a mining loop per keypair in the directory, sharing this miner's clients and caches
+    pub async fn mine_wallets(&self, args: &MineArgs, keypair_dir: &str, core_ids: &[CoreId]) {
+        let paths = match keypair_paths(keypair_dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                println!(
+                    "{}: Failed to read {}: {}",
+                    "ERROR".bold().red(),
+                    keypair_dir,
+                    err
+                );
+                return;
+            }
+        };
+        let mut wallets = vec![];
+        for path in paths {
+            match read_keypair_file(&path) {
+                Ok(keypair) => wallets.push((path.display().to_string(), keypair.pubkey())),
+                Err(_) => println!("Skipping {}: not a keypair file", path.display()),
+            }
+        }
+        if wallets.is_empty() {
+            println!("No keypairs found in {}", keypair_dir);
+            return;
+        }
+
+        // Split the threads and pinned cores evenly between wallets
+        let threads = (args.threads / wallets.len() as u64).max(1);
+        println!(
+            "Mining with {} wallets, {} thread(s) each",
+            wallets.len(),
+            threads
+        );
+        let progress = Arc::new(if self.quiet {
+            MultiProgress::with_draw_target(plain_draw_target())
+        } else {
+            MultiProgress::new()
+        });
+        let handles = wallets.into_iter().enumerate().map(|(i, (path, pubkey))| {
+            let label = format!("[{}]", &pubkey.to_string()[..8]);
+            let miner = self.for_wallet(path, label, progress.clone());
+            let args = args.clone();
+            let start = (i * threads as usize).min(core_ids.len());
+            let end = (start + threads as usize).min(core_ids.len());
+            let core_ids = core_ids[start..end].to_vec();
+            let state_file = args
+                .state_file
+                .as_ref()
+                .map(|path| format!("{}.{}", path, pubkey));
+            tokio::spawn(async move {
+                miner
+                    .mine_loop(&args, threads, &core_ids, state_file.as_deref())
+                    .await;
+            })
+        });
+
+        // Each wallet runs in its own task, so one panicking never stops the others
+        for result in join_all(handles).await {
+            if let Err(err) = result {
+                println!(
+                    "{}: Wallet mining task failed: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+            }
+        }
+        println!(
+            "Fleet fees: {} SOL",
+            lamports_to_sol(self.fees_paid.load(Ordering::Relaxed))
+        );
+    }
+
+    // A miner for another wallet sharing this one's settings, clients, caches, refill cap, and rate limits
+    fn for_wallet(
+        &self,
+        keypair_filepath: String,
+        label: String,
+        progress: Arc<MultiProgress>,
+    ) -> Miner {
+        // Keep an explicitly configured fee payer, otherwise each wallet pays for itself
+        let fee_payer_filepath = if self.fee_payer_filepath == self.keypair_filepath {
+            Some(keypair_filepath.clone())
+        } else {
+            self.fee_payer_filepath.clone()
+        };
+        // A shared fee payer is refilled by one wallet at a time
+        let refilling = if fee_payer_filepath == self.fee_payer_filepath {
+            self.refilling.clone()
+        } else {
+            Arc::new(AtomicBool::new(false))
+        };
+        Miner {
+            keypair_filepath: Some(keypair_filepath),
+            fee_payer_filepath,
+            refilling,
+            fees_paid: Arc::new(AtomicU64::new(0)),
+            fleet_fees_paid: Some(self.fees_paid.clone()),
+            label: Some(label),
+            progress: Some(progress),
+            ..self.clone()
+        }
+    }
+}